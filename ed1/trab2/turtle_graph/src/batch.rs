@@ -0,0 +1,247 @@
+use std::{
+    io,
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use crate::{generate_syntax, render, TurtleGraphConfig};
+
+/// Width in pixels of the thumbnails of the gallery.
+const GALLERY_THUMBNAIL: u32 = 240;
+
+/// What rendering a grammar of a batch gave.
+#[derive(Debug)]
+pub struct Rendered {
+    pub path: PathBuf,
+    /// Text of the grammar file, empty when it could not be read.
+    pub source: String,
+    pub result: Result<RenderStats, String>,
+}
+
+#[derive(Debug)]
+pub struct RenderStats {
+    pub symbols: usize,
+    /// Bytes of the rendered file.
+    pub bytes: usize,
+    pub milliseconds: f64,
+    /// `Stats::table` of the drawing.
+    pub table: String,
+}
+
+impl Rendered {
+    /// File name of the grammar without the extension, which names its outputs.
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// The `.lsy` files of `directory`, sorted by name.
+fn grammars(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) == Some("lsy") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Renders the grammar to `{name}.ps` and its thumbnail to `{name}.svg`.
+fn render_grammar(
+    source: &str,
+    directory: &Path,
+    name: &str,
+    output: &Path,
+) -> Result<RenderStats, String> {
+    let start = Instant::now();
+    let config =
+        TurtleGraphConfig::parse_in(source, directory).map_err(|error| error.to_string())?;
+    let syntax = generate_syntax(&config);
+    let output_file_path = output.join(format!("{}.ps", name));
+    let value = render(&config, &syntax, &output_file_path.to_string_lossy());
+    std::fs::write(&output_file_path, &value)
+        .map_err(|error| format!("{}: {}", output_file_path.display(), error))?;
+    let thumbnail_path = output.join(format!("{}.svg", name));
+    std::fs::write(&thumbnail_path, config.thumbnail_syntax().convert_svg())
+        .map_err(|error| format!("{}: {}", thumbnail_path.display(), error))?;
+    Ok(RenderStats {
+        symbols: syntax.list.iter().count(),
+        bytes: value.len(),
+        milliseconds: start.elapsed().as_secs_f64() * 1000.0,
+        table: syntax.stats().table(),
+    })
+}
+
+/// Reads and renders the grammar at `path`, turning a panic into an error.
+fn render_path(path: &Path, output: &Path) -> Rendered {
+    let mut rendered = Rendered {
+        path: path.into(),
+        source: String::new(),
+        result: Err(String::new()),
+    };
+    rendered.result = match std::fs::read_to_string(path) {
+        Ok(source) => {
+            rendered.source = source;
+            let name = rendered.name();
+            std::panic::catch_unwind(AssertUnwindSafe(|| {
+                let directory = path.parent().unwrap_or("".as_ref());
+                render_grammar(&rendered.source, directory, &name, output)
+            }))
+            .unwrap_or_else(|_| Err("rendering panicked".into()))
+        }
+        Err(error) => Err(error.to_string()),
+    };
+    rendered
+}
+
+/// Renders every `.lsy` file of `input` into `output` on `threads` threads and
+/// writes an `index.html` gallery of them. A grammar failing to parse or render,
+/// even by panicking, is reported on its entry and the others go on.
+pub fn batch(input: &Path, output: &Path, threads: usize) -> io::Result<Vec<Rendered>> {
+    let paths = grammars(input)?;
+    std::fs::create_dir_all(output)?;
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, Rendered)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, paths.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut rendered = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::SeqCst);
+                        match paths.get(i) {
+                            Some(path) => rendered.push((i, render_path(path, output))),
+                            None => return rendered,
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("panics are caught by render_path"))
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    let results: Vec<Rendered> = results.into_iter().map(|(_, rendered)| rendered).collect();
+    std::fs::write(output.join("index.html"), gallery(&results))?;
+    Ok(results)
+}
+
+/// One line per grammar and the count of failures.
+pub fn batch_summary(results: &[Rendered]) -> String {
+    let mut value = String::new();
+    let mut failed = 0;
+    for rendered in results {
+        match &rendered.result {
+            Ok(stats) => value.push_str(&format!(
+                "ok     {} ({} symbols, {:.0} ms)\n",
+                rendered.path.display(),
+                stats.symbols,
+                stats.milliseconds
+            )),
+            Err(error) => {
+                failed += 1;
+                value.push_str(&format!("error  {}: {}\n", rendered.path.display(), error));
+            }
+        }
+    }
+    value.push_str(&format!(
+        "{} of {} grammars rendered, {} failed\n",
+        results.len() - failed,
+        results.len(),
+        failed
+    ));
+    value
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A page with the thumbnail, the grammar and the numbers of every drawing.
+fn gallery(results: &[Rendered]) -> String {
+    let mut value = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>turtle_graph gallery</title>\n<style>\nbody { font-family: sans-serif; display: flex; flex-wrap: wrap; }\nfigure { width: 320px; margin: 1em; }\npre { font-size: 12px; white-space: pre-wrap; }\n.error { color: #b00; }\n</style>\n</head>\n<body>\n",
+    );
+    for rendered in results {
+        let name = escape(&rendered.name());
+        value.push_str(&format!("<figure id=\"{}\">\n", name));
+        match &rendered.result {
+            Ok(stats) => {
+                value.push_str(&format!(
+                    "<a href=\"{name}.ps\"><img src=\"{name}.svg\" width=\"{size}\" height=\"{size}\" alt=\"{name}\"></a>\n",
+                    name = name,
+                    size = GALLERY_THUMBNAIL
+                ));
+                value.push_str(&format!("<figcaption>{}</figcaption>\n", name));
+                value.push_str(&format!(
+                    "<pre>symbols: {}\nfile: {} bytes\ntime: {:.0} ms\n{}</pre>\n",
+                    stats.symbols,
+                    stats.bytes,
+                    stats.milliseconds,
+                    escape(&stats.table)
+                ));
+            }
+            Err(error) => {
+                value.push_str(&format!("<figcaption>{}</figcaption>\n", name));
+                value.push_str(&format!("<pre class=\"error\">{}</pre>\n", escape(error)));
+            }
+        }
+        value.push_str(&format!(
+            "<pre>{}</pre>\n</figure>\n",
+            escape(&rendered.source)
+        ));
+    }
+    value.push_str("</body>\n</html>\n");
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp::TempDir;
+
+    #[test]
+    fn gallery() {
+        let temp = TempDir::new("batch");
+        let input = temp.path().join("input");
+        let output = temp.path().join("output");
+        std::fs::create_dir_all(&input).unwrap();
+        std::fs::write(
+            input.join("koch.lsy"),
+            "angle 6\norder 2\naxiom F\nF = F+F--F+F\n",
+        )
+        .unwrap();
+        std::fs::write(input.join("broken.lsy"), "angle <six>\n").unwrap();
+        std::fs::write(input.join("square.lsy"), "angle 4\naxiom F+F+F+F\n").unwrap();
+        std::fs::write(input.join("notes.txt"), "not a grammar").unwrap();
+
+        let results = batch(&input, &output, 2).unwrap();
+        let names: Vec<String> = results.iter().map(Rendered::name).collect();
+        assert_eq!(names, ["broken", "koch", "square"]);
+        assert!(results[0].result.is_err());
+        assert_eq!(results[1].result.as_ref().unwrap().symbols, 36);
+        assert!(std::fs::read_to_string(output.join("koch.ps"))
+            .unwrap()
+            .ends_with("showpage\nquit\n"));
+        assert!(output.join("square.svg").exists());
+
+        let summary = batch_summary(&results);
+        assert!(summary.starts_with("error  "));
+        assert!(summary.ends_with("2 of 3 grammars rendered, 1 failed\n"));
+        let index = std::fs::read_to_string(output.join("index.html")).unwrap();
+        assert!(index.contains("<img src=\"koch.svg\""));
+        assert!(index.contains("<pre>angle &lt;six&gt;\n</pre>"));
+        assert!(index.contains("segments: 16\n"));
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Display;
+
+const BASE: u64 = 1 << 32;
+
+/// Unsigned integer of any size, enough for counting symbols of deep derivations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BigUint {
+    /// Little endian base 2^32 digits without trailing zeros.
+    digits: Vec<u32>,
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        let mut value = Self {
+            digits: vec![value as u32, (value >> 32) as u32],
+        };
+        value.trim();
+        value
+    }
+}
+
+impl BigUint {
+    fn trim(&mut self) {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let mut digits = Vec::with_capacity(self.digits.len().max(other.digits.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..self.digits.len().max(other.digits.len()) {
+            let sum = carry
+                + *self.digits.get(i).unwrap_or(&0) as u64
+                + *other.digits.get(i).unwrap_or(&0) as u64;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+        digits.push(carry as u32);
+        let mut value = Self { digits };
+        value.trim();
+        value
+    }
+
+    pub fn mul_small(&self, factor: u32) -> BigUint {
+        let mut digits = Vec::with_capacity(self.digits.len() + 1);
+        let mut carry = 0u64;
+        for digit in &self.digits {
+            let product = *digit as u64 * factor as u64 + carry;
+            digits.push(product as u32);
+            carry = product >> 32;
+        }
+        digits.push(carry as u32);
+        let mut value = Self { digits };
+        value.trim();
+        value
+    }
+
+    /// Divides in place returning the remainder.
+    fn div_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for digit in self.digits.iter_mut().rev() {
+            let value = (remainder << 32) + *digit as u64;
+            *digit = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        self.trim();
+        remainder as u32
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.digits
+            .iter()
+            .rev()
+            .fold(0.0, |value, digit| value * BASE as f64 + *digit as f64)
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.iter().rev().cmp(other.digits.iter().rev()))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return f.pad("0");
+        }
+        let mut value = self.clone();
+        let mut chunks = Vec::new();
+        while !value.is_zero() {
+            chunks.push(value.div_small(1_000_000_000));
+        }
+        let mut text = chunks.pop().map(|c| c.to_string()).unwrap_or_default();
+        for chunk in chunks.iter().rev() {
+            text.push_str(&format!("{:09}", chunk));
+        }
+        f.pad(&text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let mut value = BigUint::from(1);
+        for _ in 0..100 {
+            value = value.mul_small(2);
+        }
+        assert_eq!(value.to_string(), "1267650600228229401496703205376");
+        assert_eq!(
+            value.add(&value).add(&BigUint::from(u64::MAX)).to_string(),
+            "2535301200474905547067115962367"
+        );
+        assert_eq!(format!("{:>4}", BigUint::default()), "   0");
+        assert!(value > BigUint::from(u64::MAX));
+        assert!(BigUint::from(3) < BigUint::from(4));
+        assert!((value.to_f64() - 2f64.powi(100)).abs() < 1e15);
+    }
+}
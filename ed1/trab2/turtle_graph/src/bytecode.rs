@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use crate::{turtle::Turtle, Colors, TurtleSymbol, TurtleSyntax, Walk};
+
+// Opcodes. The moves and turns are followed by how many times they repeat and
+// `COLOR` by the digit of its `C`, as read by `Colors::from_digit`.
+const DRAW: u8 = 0;
+const MOVE: u8 = 1;
+const LEFT: u8 = 2;
+const RIGHT: u8 = 3;
+const PUSH: u8 = 4;
+const POP: u8 = 5;
+const QUERY: u8 = 6;
+const COLOR: u8 = 7;
+
+/// Turtle commands of a syntax packed in bytes. Runs of the same move or turn
+/// take a single opcode, a `C` and its digit become the color they select and
+/// symbols the turtle ignores are left out.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Program {
+    code: Vec<u8>,
+    /// Segments drawn when run, so their buffer is allocated once.
+    strokes: usize,
+}
+
+/// Counts are written 7 bits at a time, lowest first, the high bit telling
+/// whether another byte follows.
+fn write_count(code: &mut Vec<u8>, mut count: usize) {
+    while count >= 0x80 {
+        code.push(count as u8 | 0x80);
+        count >>= 7;
+    }
+    code.push(count as u8);
+}
+
+fn read_count(code: &[u8], pc: &mut usize) -> usize {
+    let mut count = 0;
+    let mut shift = 0;
+    loop {
+        let byte = code[*pc];
+        *pc += 1;
+        count |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return count;
+        }
+        shift += 7;
+    }
+}
+
+impl Program {
+    /// Compiles `symbols`, each one running the command `draws` gives it.
+    pub fn compile(
+        symbols: impl IntoIterator<Item = TurtleSymbol>,
+        draws: &HashMap<TurtleSymbol, TurtleSymbol>,
+    ) -> Program {
+        let mut program = Program::default();
+        // The repeated opcode not written yet and how many times it repeats.
+        let mut run: Option<(u8, usize)> = None;
+        let mut symbols = symbols.into_iter();
+        while let Some(symbol) = symbols.next() {
+            let (op, color) = match symbol.command(draws) {
+                TurtleSymbol::F => (DRAW, None),
+                TurtleSymbol::G => (MOVE, None),
+                TurtleSymbol::Plus => (LEFT, None),
+                TurtleSymbol::Minus => (RIGHT, None),
+                TurtleSymbol::PushStack => (PUSH, None),
+                TurtleSymbol::PopStack => (POP, None),
+                TurtleSymbol::Query => (QUERY, None),
+                // The symbol after a `C` is taken even when it is not a color.
+                TurtleSymbol::CustomSymbol('C') => match symbols.next() {
+                    Some(TurtleSymbol::CustomSymbol(digit))
+                        if Colors::from_digit(digit).is_some() =>
+                    {
+                        (COLOR, digit.to_digit(10))
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if let Some((last, count)) = &mut run {
+                if *last == op {
+                    *count += 1;
+                    continue;
+                }
+            }
+            if let Some((last, count)) = run.take() {
+                program.write_run(last, count);
+            }
+            match (op, color) {
+                (DRAW, _) | (MOVE, _) | (LEFT, _) | (RIGHT, _) => run = Some((op, 1)),
+                (COLOR, Some(index)) => program.code.extend([COLOR, index as u8]),
+                _ => program.code.push(op),
+            }
+        }
+        if let Some((last, count)) = run {
+            program.write_run(last, count);
+        }
+        program
+    }
+
+    fn write_run(&mut self, op: u8, count: usize) {
+        self.code.push(op);
+        write_count(&mut self.code, count);
+        if op == DRAW {
+            self.strokes += count;
+        }
+    }
+
+    /// Bytes taken by the program.
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    /// Runs the program from where `turtle` is, adding what it draws to `walk`.
+    pub(crate) fn run(&self, turtle: &mut Turtle, walk: &mut Walk) {
+        let code = &self.code[..];
+        walk.segments.reserve(self.strokes);
+        let mut pc = 0;
+        while pc < code.len() {
+            let op = code[pc];
+            pc += 1;
+            match op {
+                DRAW => turtle.forward_run(read_count(code, &mut pc), &mut walk.segments),
+                MOVE => {
+                    for _ in 0..read_count(code, &mut pc) {
+                        turtle.jump();
+                    }
+                }
+                LEFT | RIGHT => {
+                    for _ in 0..read_count(code, &mut pc) {
+                        turtle.turn(op == LEFT);
+                    }
+                }
+                PUSH => walk.max_depth = walk.max_depth.max(turtle.push()),
+                POP => {
+                    if turtle.pop() == Some(true) {
+                        walk.tips += 1;
+                    }
+                }
+                QUERY => walk.queries.push(turtle.position()),
+                COLOR => {
+                    if let Some(color) = Colors::from_digit(char::from(b'0' + code[pc])) {
+                        turtle.set_color(color);
+                    }
+                    pc += 1;
+                }
+                _ => unreachable!("unknown opcode {}", op),
+            }
+        }
+        turtle.finish(walk);
+    }
+
+    /// One instruction per line, like `DRAW 4` or `COLOR 1`.
+    pub fn disassemble(&self) -> String {
+        let code = &self.code[..];
+        let mut value = String::new();
+        let mut pc = 0;
+        while pc < code.len() {
+            let op = code[pc];
+            pc += 1;
+            let name = [
+                "DRAW", "MOVE", "LEFT", "RIGHT", "PUSH", "POP", "QUERY", "COLOR",
+            ][op as usize];
+            value.push_str(name);
+            match op {
+                DRAW | MOVE | LEFT | RIGHT => {
+                    value.push_str(&format!(" {}", read_count(code, &mut pc)))
+                }
+                COLOR => {
+                    value.push_str(&format!(" {}", code[pc]));
+                    pc += 1;
+                }
+                _ => {}
+            }
+            value.push('\n');
+        }
+        value
+    }
+}
+
+impl TurtleSyntax {
+    /// The symbols of the syntax compiled for the turtle.
+    pub fn compile(&self) -> Program {
+        Program::compile(self.list.iter().map(|node| node.data), &self.draws)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::TurtleGraphConfig;
+
+    #[test]
+    fn compile() {
+        let config =
+            TurtleGraphConfig::from_str("angle 4\ndraw {leaf} F\naxiom C2FFXF++[G?C9{leaf}]C1-")
+                .unwrap();
+        let program = config.generate_syntax().compile();
+        assert_eq!(
+            program.disassemble(),
+            "COLOR 2\nDRAW 3\nLEFT 2\nPUSH\nMOVE 1\nQUERY\nDRAW 1\nPOP\nCOLOR 1\nRIGHT 1\n"
+        );
+        assert_eq!(program.len(), 17);
+        let mut code = Vec::new();
+        write_count(&mut code, 300);
+        assert_eq!(code, [0xac, 0x02]);
+        assert_eq!(read_count(&code, &mut 0), 300);
+    }
+
+    /// Both the program and the symbols walked one by one draw the same.
+    fn assert_same_walk(text: &str) {
+        let syntax = TurtleGraphConfig::from_str(text).unwrap().generate_syntax();
+        let walk = syntax.walk_parallel(1);
+        let reference = syntax.walk_symbols(syntax.list.iter().map(|node| node.data));
+        assert_eq!(walk.segments, reference.segments);
+        assert_eq!(walk.queries, reference.queries);
+        assert_eq!(walk.tips, reference.tips);
+        assert_eq!(walk.max_depth, reference.max_depth);
+        assert_eq!(walk.end, reference.end);
+        assert_eq!(walk.heading, reference.heading);
+    }
+
+    #[test]
+    fn run() {
+        let text = "angle 14\norder 5\naxiom X\nX = C1F[+X?]C2F[-X]+XCG\nF = FF\n";
+        assert_same_walk(text);
+        assert_same_walk(&format!("{}angle_jitter 20\nlength_jitter 10\n", text));
+        assert_same_walk(&format!("{}tropism 0 -1 0.1\n", text));
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::Display;
+
+use crate::{parse_symbol, symbols_to_string, Point, TurtleGraphConfig, TurtleSymbol};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variable {
+    /// Index of the derivation step, 0 when rewriting the axiom.
+    Time,
+    /// Derivation steps the symbol went through without being rewritten.
+    Age,
+    /// Where the turtle reached a `?` on the last generation, on the page like the
+    /// region. Only `?`, also written `?P(x,y)`, knows them.
+    X,
+    Y,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+/// Operators in the order they are tried, longer ones first.
+const COMPARISONS: [(&str, Comparison); 6] = [
+    ("<=", Comparison::LessEqual),
+    (">=", Comparison::GreaterEqual),
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    ("<", Comparison::Less),
+    (">", Comparison::Greater),
+];
+
+/// When a conditional rule applies, like `t < 3`, `age >= 2` or `y > -50.5`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub variable: Variable,
+    pub comparison: Comparison,
+    pub value: f32,
+}
+
+/// A rule like `F : t < 3 = FF`, taking over the plain rule of its symbol while
+/// its condition holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionalRule {
+    pub symbol: TurtleSymbol,
+    pub condition: Condition,
+    pub value: Vec<TurtleSymbol>,
+}
+
+impl Condition {
+    /// Parses a condition without spaces at the start of `s`, returning the rest.
+    pub fn parse_prefix(s: &str) -> Option<(Self, &str)> {
+        let (variable, s) = if let Some(s) = s.strip_prefix("age") {
+            (Variable::Age, s)
+        } else if let Some(s) = s.strip_prefix('x') {
+            (Variable::X, s)
+        } else if let Some(s) = s.strip_prefix('y') {
+            (Variable::Y, s)
+        } else {
+            (Variable::Time, s.strip_prefix('t')?)
+        };
+        let (operator, comparison) = COMPARISONS
+            .iter()
+            .find(|(operator, _)| s.starts_with(operator))?;
+        let s = &s[operator.len()..];
+        let sign = usize::from(s.starts_with('-'));
+        let digits = s[sign..]
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .map_or(s.len(), |end| sign + end);
+        let value = s[..digits].parse().ok()?;
+        Some((
+            Self {
+                variable,
+                comparison: *comparison,
+                value,
+            },
+            &s[digits..],
+        ))
+    }
+
+    /// Whether the condition holds on step `time` for a symbol of `age`, found at
+    /// `position` when it is a `?` the turtle reached.
+    pub fn holds(&self, time: usize, age: usize, position: Option<Point>) -> bool {
+        let variable = match (self.variable, position) {
+            (Variable::Time, _) => time as f32,
+            (Variable::Age, _) => age as f32,
+            (Variable::X, Some(position)) => position.x,
+            (Variable::Y, Some(position)) => position.y,
+            (Variable::X, None) | (Variable::Y, None) => return false,
+        };
+        match self.comparison {
+            Comparison::Less => variable < self.value,
+            Comparison::LessEqual => variable <= self.value,
+            Comparison::Greater => variable > self.value,
+            Comparison::GreaterEqual => variable >= self.value,
+            Comparison::Equal => variable == self.value,
+            Comparison::NotEqual => variable != self.value,
+        }
+    }
+
+    pub fn is_position(&self) -> bool {
+        matches!(self.variable, Variable::X | Variable::Y)
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variable = match self.variable {
+            Variable::Time => "t",
+            Variable::Age => "age",
+            Variable::X => "x",
+            Variable::Y => "y",
+        };
+        let operator = COMPARISONS
+            .iter()
+            .find(|(_, comparison)| *comparison == self.comparison)
+            .map(|(operator, _)| *operator)
+            .unwrap_or("");
+        write!(f, "{} {} {}", variable, operator, self.value)
+    }
+}
+
+impl ConditionalRule {
+    /// The left side of the rule, `F : t < 3`.
+    pub fn head(&self) -> String {
+        format!("{} : {}", self.symbol, self.condition)
+    }
+
+    /// Parses the left side of a rule with its spaces removed, `F:t<3`, returning
+    /// what follows it. Conditions on the position are only taken on `?`.
+    pub fn parse_head(rule: &str) -> Option<(TurtleSymbol, Condition, &str)> {
+        let (symbol, rest) = parse_symbol(rule).ok()??;
+        let (condition, rest) = Condition::parse_prefix(rest.strip_prefix(':')?)?;
+        if condition.is_position() && symbol != TurtleSymbol::Query {
+            return None;
+        }
+        Some((symbol, condition, rest))
+    }
+}
+
+impl Display for ConditionalRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.head(), symbols_to_string(&self.value))
+    }
+}
+
+impl TurtleGraphConfig {
+    /// What `symbol` is rewritten into on step `time` after surviving `age` steps,
+    /// the first conditional rule that holds wins over the plain rule. `position`
+    /// is where the turtle reached the symbol when it is a `?`.
+    pub fn production(
+        &self,
+        symbol: &TurtleSymbol,
+        time: usize,
+        age: usize,
+        position: Option<Point>,
+    ) -> Option<&[TurtleSymbol]> {
+        self.conditional_rules
+            .iter()
+            .find(|rule| rule.symbol == *symbol && rule.condition.holds(time, age, position))
+            .map(|rule| rule.value.as_slice())
+            .or_else(|| self.rules.get(symbol).map(|value| value.as_slice()))
+    }
+
+    /// Whether some rule asks where the turtle reached a `?`, every generation is
+    /// then drawn before being rewritten.
+    pub(crate) fn queries_position(&self) -> bool {
+        self.conditional_rules
+            .iter()
+            .any(|rule| rule.condition.is_position())
+    }
+
+    /// Page positions of the turtle at each `?` of `symbols`, in order.
+    pub(crate) fn query_positions(
+        &self,
+        symbols: impl Iterator<Item = TurtleSymbol>,
+    ) -> Vec<Point> {
+        let syntax = self.axiom_syntax();
+        syntax
+            .walk_symbols(symbols)
+            .queries
+            .iter()
+            .map(|point| point.rotated(syntax.rotate))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let (condition, rest) = Condition::parse_prefix("age>=2=FF").unwrap();
+        assert_eq!(condition.to_string(), "age >= 2");
+        assert_eq!(rest, "=FF");
+        assert!(condition.holds(0, 2, None) && !condition.holds(5, 1, None));
+        assert_eq!(Condition::parse_prefix("t=<2"), None);
+        assert_eq!(Condition::parse_prefix("z<2"), None);
+        let (condition, rest) = Condition::parse_prefix("y>-50.5=%").unwrap();
+        assert_eq!((condition.to_string().as_str(), rest), ("y > -50.5", "=%"));
+        assert!(condition.holds(0, 0, Some(Point::new(0.0, -2.0))));
+        assert!(!condition.holds(0, 0, None));
+        assert_eq!(ConditionalRule::parse_head("F:x<2=F"), None);
+        assert_eq!(
+            ConditionalRule::parse_head("F:t!=1=F").map(|(_, c, rest)| (c.to_string(), rest)),
+            Some(("t != 1".into(), "=F"))
+        );
+    }
+
+    #[test]
+    fn phases() {
+        let config = TurtleGraphConfig::from_str(
+            "order 4\naxiom A\nA : t < 2 = FA\nA : t >= 2 = [F]A\nF : age == 1 = FF",
+        )
+        .unwrap();
+        // F written on step 0 doubles on step 2, the one written on step 1 on step 3.
+        assert_eq!(config.generate_syntax().string(), "FFFF[F][F]A");
+        let tree = config.derivation_tree();
+        let leaves: Vec<TurtleSymbol> = tree.leaves().iter().map(|&i| tree.get(i).data).collect();
+        assert_eq!(
+            symbols_to_string(&leaves),
+            config.generate_syntax().string()
+        );
+        assert_eq!(
+            config.to_string(),
+            "order 4\naxiom A\nA : t < 2 = FA\nA : t >= 2 = [F]A\nF : age == 1 = FF\n"
+        );
+    }
+
+    #[test]
+    fn positions() {
+        // The stem grows up, the `?` reached 200 units high or more grows a branch.
+        let config = TurtleGraphConfig::from_str(
+            "angle 4\norder 3\nrotate 90\naxiom A\nA = F?P(x, y)A\n?P(x,y) : y >= 200 = [+F]",
+        )
+        .unwrap();
+        assert_eq!(config.generate_syntax().string(), "F?F[+F]F?A");
+        let tree = config.derivation_tree();
+        let leaves: Vec<TurtleSymbol> = tree.leaves().iter().map(|&i| tree.get(i).data).collect();
+        assert_eq!(symbols_to_string(&leaves), "F?F[+F]F?A");
+        assert!(config.to_string().ends_with("? : y >= 200 = [+F]\n"));
+        assert!(config.growth().conditions_ignored);
+        assert!(TurtleGraphConfig::from_str("A : x > 0 = F").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{TurtleGraphConfig, TurtleSymbol, TurtleSyntax};
+
+/// Goes over a string telling which symbols are left once every `%` removed the
+/// rest of its branch, up to the `]` closing it.
+#[derive(Debug, Default)]
+pub(crate) struct Cutter {
+    /// Branches opened since the last `%`, while cutting.
+    depth: Option<usize>,
+}
+
+impl Cutter {
+    pub(crate) fn keep(&mut self, symbol: &TurtleSymbol) -> bool {
+        match (self.depth, symbol) {
+            (None, TurtleSymbol::Cut) => self.depth = Some(0),
+            (None, _) => return true,
+            (Some(depth), TurtleSymbol::PushStack) => self.depth = Some(depth + 1),
+            (Some(0), TurtleSymbol::PopStack) => {
+                self.depth = None;
+                return true;
+            }
+            (Some(depth), TurtleSymbol::PopStack) => self.depth = Some(depth - 1),
+            _ => {}
+        }
+        false
+    }
+}
+
+impl TurtleSyntax {
+    /// Removes every `%` with the rest of its branch.
+    pub fn cut(&mut self) {
+        let mut cutter = Cutter::default();
+        self.list.retain(|symbol| cutter.keep(symbol));
+    }
+}
+
+impl TurtleGraphConfig {
+    /// Whether a derivation step can remove symbols, either written by a rule as
+    /// `%` or left by a `?` outside the region. A `%` of the axiom cuts on the
+    /// first step, like one written by a rule.
+    pub(crate) fn prunes(&self) -> bool {
+        let rules = self.rules.values();
+        let conditional = self.conditional_rules.iter().map(|rule| &rule.value);
+        self.region.is_some()
+            || std::iter::once(&self.axiom)
+                .chain(rules)
+                .chain(conditional)
+                .flatten()
+                .any(|symbol| *symbol == TurtleSymbol::Cut)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::symbols_to_string;
+
+    #[test]
+    fn cutter() {
+        let mut cutter = Cutter::default();
+        let symbols: Vec<TurtleSymbol> = "F[+F%F[F]F]G%F".chars().map(TurtleSymbol::from).collect();
+        let kept: Vec<TurtleSymbol> = symbols
+            .into_iter()
+            .filter(|symbol| cutter.keep(symbol))
+            .collect();
+        assert_eq!(symbols_to_string(&kept), "F[+F]G");
+    }
+
+    #[test]
+    fn shedding() {
+        // Every branch is shed two steps after it sprouts.
+        let config =
+            TurtleGraphConfig::from_str("order 4\naxiom A\nA = F[S+L]A\nS = T\nT = %\nL = FL")
+                .unwrap();
+        assert!(config.prunes());
+        let expected = "F[]F[]F[T+FL]F[S+L]A";
+        assert_eq!(config.generate_syntax().string(), expected);
+        let conditional =
+            TurtleGraphConfig::from_str(&format!("{}\nG : t > 9 = G", config)).unwrap();
+        assert_eq!(conditional.generate_syntax().string(), expected);
+        assert_eq!(
+            config.derivation(),
+            "0: A\n1: F[S+L]A\n2: F[T+FL]F[S+L]A\n3: F[]F[T+FL]F[S+L]A\n4: F[]F[]F[T+FL]F[S+L]A\n"
+        );
+        assert_eq!(
+            config.generation_syntax().string(),
+            "C1F[]C2F[]C3F[T+C4FL]C4F[S+L]A"
+        );
+        assert_eq!(config.trace().matches("  cut: 5 symbols\n").count(), 2);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+
+use crate::{Segment, TurtleGraphConfig, TurtleSymbol};
+
+/// Fewest box sizes worth fitting a line through.
+const MIN_BOX_SIZES: usize = 3;
+
+/// Samples taken along a segment per box it crosses, enough to not skip a box
+/// when the segment only clips its corner.
+const SAMPLES_PER_BOX: f64 = 4.0;
+
+/// Boxes touched by the drawing at each box size, and the line fitted through
+/// `ln(1 / size)` against `ln(boxes)`.
+#[derive(Debug)]
+pub struct BoxCounting {
+    pub boxes: Vec<(f64, usize)>,
+    /// Slope of the fit, the estimated dimension.
+    pub dimension: f64,
+    pub r_squared: f64,
+}
+
+/// Least squares line through the points, returning its slope and R².
+fn fit(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 {
+        1.0
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+    (slope, r_squared)
+}
+
+/// Counts the boxes touched by the segments on grids halving from half the size
+/// of the drawing down to the shortest segment, below which every drawing looks
+/// like straight lines. Drawings too small for `MIN_BOX_SIZES` give `None`.
+pub fn box_counting(segments: &[Segment]) -> Option<BoxCounting> {
+    let length = |s: &Segment| {
+        ((s.to.x as f64 - s.from.x as f64).powi(2) + (s.to.y as f64 - s.from.y as f64).powi(2))
+            .sqrt()
+    };
+    let shortest = segments
+        .iter()
+        .map(length)
+        .filter(|l| *l > 0.0)
+        .fold(f64::INFINITY, f64::min);
+    let points = segments.iter().flat_map(|s| vec![s.from, s.to]);
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for point in points {
+        min_x = min_x.min(point.x as f64);
+        min_y = min_y.min(point.y as f64);
+        max_x = max_x.max(point.x as f64);
+        max_y = max_y.max(point.y as f64);
+    }
+    let extent = (max_x - min_x).max(max_y - min_y);
+
+    let mut boxes = Vec::new();
+    let mut size = extent / 2.0;
+    while size >= shortest {
+        // Points on the far edge of the drawing go on the last box instead of
+        // starting a new one.
+        let cell = |value: f64, min: f64, max: f64| {
+            let last = (((max - min) / size).ceil() as i64 - 1).max(0);
+            (((value - min) / size).floor() as i64).min(last)
+        };
+        let mut touched = HashSet::new();
+        for segment in segments {
+            let samples = (length(segment) / size * SAMPLES_PER_BOX).ceil().max(1.0) as usize;
+            for i in 0..=samples {
+                let t = i as f64 / samples as f64;
+                let x = segment.from.x as f64 + (segment.to.x - segment.from.x) as f64 * t;
+                let y = segment.from.y as f64 + (segment.to.y - segment.from.y) as f64 * t;
+                touched.insert((cell(x, min_x, max_x), cell(y, min_y, max_y)));
+            }
+        }
+        boxes.push((size, touched.len()));
+        size /= 2.0;
+    }
+    if boxes.len() < MIN_BOX_SIZES {
+        return None;
+    }
+
+    let points: Vec<(f64, f64)> = boxes
+        .iter()
+        .map(|(size, count)| ((1.0 / size).ln(), (*count as f64).ln()))
+        .collect();
+    let (dimension, r_squared) = fit(&points);
+    Some(BoxCounting {
+        boxes,
+        dimension,
+        r_squared,
+    })
+}
+
+impl TurtleGraphConfig {
+    /// Dimension `ln(n) / ln(1 / r)` of a curve whose only rule replaces `F` with `n`
+    /// copies of itself scaled by `r`, the distance from the start to the end of the
+    /// production measured in steps of `F`. Branching productions and ones moving
+    /// with `G` are not self-similar this way and give `None`.
+    pub fn similarity_dimension(&self) -> Option<f64> {
+        if self.rules.len() != 1
+            || !self.conditional_rules.is_empty()
+            || !self.interpretations.is_empty()
+        {
+            return None;
+        }
+        let production = self.rules.get(&TurtleSymbol::F)?;
+        let base_angle = std::f64::consts::TAU / self.angle.unwrap_or(0).max(1) as f64;
+        let (mut x, mut y, mut angle) = (0f64, 0f64, 0f64);
+        let mut copies = 0;
+        let mut symbols = production.iter();
+        while let Some(symbol) = symbols.next() {
+            match symbol {
+                TurtleSymbol::F => {
+                    copies += 1;
+                    x += angle.cos();
+                    y += angle.sin();
+                }
+                TurtleSymbol::Plus => angle += base_angle,
+                TurtleSymbol::Minus => angle -= base_angle,
+                TurtleSymbol::CustomSymbol('C') => {
+                    symbols.next();
+                }
+                TurtleSymbol::Query | TurtleSymbol::CustomSymbol(_) | TurtleSymbol::Named(_) => {}
+                _ => return None,
+            }
+        }
+        let scale = (x * x + y * y).sqrt();
+        if copies < 2 || scale <= 1.0 + 1e-9 {
+            return None;
+        }
+        Some((copies as f64).ln() / scale.ln())
+    }
+}
+
+/// Box counting table of the drawing followed by the similarity dimension when
+/// the grammar has one.
+pub fn dimension_report(config: &TurtleGraphConfig) -> String {
+    let mut value = String::new();
+    match box_counting(&config.generate_syntax().segments()) {
+        Some(counting) => {
+            value.push_str(&format!("{:>12}  {:>10}\n", "box size", "boxes"));
+            for (size, count) in &counting.boxes {
+                value.push_str(&format!("{:>12.2}  {:>10}\n", size, count));
+            }
+            value.push_str(&format!(
+                "box-counting dimension: {:.4} (R\u{b2} {:.4})\n",
+                counting.dimension, counting.r_squared
+            ));
+        }
+        None => value.push_str("box-counting dimension: drawing too small, raise the order\n"),
+    }
+    if let Some(dimension) = config.similarity_dimension() {
+        value.push_str(&format!("similarity dimension: {:.4}\n", dimension));
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{Colors, Point};
+
+    #[test]
+    fn line() {
+        let segments: Vec<Segment> = (0..64)
+            .map(|i| {
+                Segment::new(
+                    Point::new(i as f32, 0.0),
+                    Point::new(i as f32 + 1.0, 0.0),
+                    Colors::Black,
+                )
+            })
+            .collect();
+        let counting = box_counting(&segments).unwrap();
+        assert_eq!(counting.boxes.len(), 6);
+        assert!((counting.dimension - 1.0).abs() < 0.05);
+        assert!(counting.r_squared > 0.99);
+        assert!(box_counting(&segments[..2]).is_none());
+    }
+
+    #[test]
+    fn koch() {
+        let config =
+            TurtleGraphConfig::from_str("angle 6\norder 5\naxiom F\nF = C1F+F--F+F").unwrap();
+        let similarity = config.similarity_dimension().unwrap();
+        assert!((similarity - 4f64.ln() / 3f64.ln()).abs() < 1e-9);
+        let counting = box_counting(&config.generate_syntax().segments()).unwrap();
+        assert!((counting.dimension - similarity).abs() < 0.1);
+
+        let tree = TurtleGraphConfig::from_str("angle 8\naxiom F\nF = F[+F]F").unwrap();
+        assert_eq!(tree.similarity_dimension(), None);
+    }
+}
@@ -0,0 +1,459 @@
+use std::{
+    io::{self, BufRead, Write},
+    ops::RangeInclusive,
+    path::PathBuf,
+};
+
+use crate::{symbols_to_string, Random, TurtleGraphConfig, TurtleSymbol, TurtleSyntax};
+
+/// Variants bred on each generation when `--count` is not given.
+pub const DEFAULT_VARIANTS: usize = 12;
+
+/// Most symbols a thumbnail is drawn from, variants growing past it are drawn on
+/// the last generation under it.
+const THUMBNAIL_SYMBOLS: u64 = 50_000;
+
+/// Angles a mutation may move the grammar to.
+const ANGLES: RangeInclusive<u8> = 3..=36;
+
+const THUMBNAIL_SIZE: u32 = 200;
+const SHEET_COLUMNS: usize = 4;
+/// Room around each thumbnail, the number of the variant goes above it.
+const SHEET_MARGIN: u32 = 24;
+
+const HELP: &str = r#"Type the numbers of the variants to breed the next generation from, like `2 5 7`.
+    save {number}...    writes variants to .lsy files
+    quit                ends the session"#;
+
+/// Index of the bracket pairing with the one at `i`, `None` when `i` is not a
+/// bracket or nothing pairs with it.
+fn partner(value: &[TurtleSymbol], i: usize) -> Option<usize> {
+    let (open, close) = match value[i] {
+        TurtleSymbol::PushStack => (TurtleSymbol::PushStack, TurtleSymbol::PopStack),
+        TurtleSymbol::PopStack => (TurtleSymbol::PopStack, TurtleSymbol::PushStack),
+        _ => return None,
+    };
+    let mut depth = 0;
+    let mut indices: Box<dyn Iterator<Item = usize>> = if open == TurtleSymbol::PushStack {
+        Box::new(i..value.len())
+    } else {
+        Box::new((0..=i).rev())
+    };
+    indices.find(|j| {
+        if value[*j] == open {
+            depth += 1;
+        } else if value[*j] == close {
+            depth -= 1;
+        }
+        depth == 0
+    })
+}
+
+/// Whether the symbol at `i` is a `C` or the digit after it, which go together.
+fn is_color(value: &[TurtleSymbol], i: usize) -> bool {
+    let c = TurtleSymbol::CustomSymbol('C');
+    value[i] == c || (i > 0 && value[i - 1] == c)
+}
+
+/// Inserts, deletes or replaces a symbol of `value`, or adds a branch to it. Colors
+/// are left as they are.
+fn mutate_production(
+    value: &mut Vec<TurtleSymbol>,
+    alphabet: &[TurtleSymbol],
+    random: &mut Random,
+) {
+    let pick = |random: &mut Random| alphabet[random.below(alphabet.len())];
+    // Never between a `C` and its digit.
+    let mut at = random.below(value.len() + 1);
+    if at > 0 && value[at - 1] == TurtleSymbol::CustomSymbol('C') {
+        at -= 1;
+    }
+    let removable: Vec<usize> = (0..value.len()).filter(|i| !is_color(value, *i)).collect();
+    let symbols: Vec<usize> = removable
+        .iter()
+        .copied()
+        .filter(|i| !matches!(value[*i], TurtleSymbol::PushStack | TurtleSymbol::PopStack))
+        .collect();
+    match random.below(4) {
+        1 => {
+            let turn = [TurtleSymbol::Plus, TurtleSymbol::Minus][random.below(2)];
+            let branch = [
+                TurtleSymbol::PushStack,
+                turn,
+                pick(random),
+                TurtleSymbol::PopStack,
+            ];
+            value.splice(at..at, branch);
+        }
+        2 if !removable.is_empty() && value.len() > 1 => {
+            // A bracket goes with the one closing it, the branch itself stays.
+            let i = removable[random.below(removable.len())];
+            match partner(value, i) {
+                Some(j) => {
+                    value.remove(i.max(j));
+                    value.remove(i.min(j));
+                }
+                None => {
+                    value.remove(i);
+                }
+            }
+        }
+        3 if !symbols.is_empty() => value[symbols[random.below(symbols.len())]] = pick(random),
+        _ => value.insert(at, pick(random)),
+    }
+}
+
+impl TurtleGraphConfig {
+    /// The syntax of the grammar on the last generation a thumbnail can be quickly
+    /// drawn from.
+    pub fn thumbnail_syntax(&self) -> TurtleSyntax {
+        self.truncated(THUMBNAIL_SYMBOLS).generate_syntax()
+    }
+
+    /// Symbols a mutation may write: `F`, `+`, `-` and every symbol of the grammar
+    /// but the brackets, which are only written in pairs, and the colors.
+    fn alphabet(&self) -> Vec<TurtleSymbol> {
+        let mut alphabet = vec![TurtleSymbol::F, TurtleSymbol::Plus, TurtleSymbol::Minus];
+        let symbols = self
+            .axiom
+            .iter()
+            .chain(self.rules.keys())
+            .chain(self.rules.values().flatten());
+        let mut colors = vec![TurtleSymbol::CustomSymbol('C')];
+        for value in std::iter::once(&self.axiom).chain(self.rules.values()) {
+            for (i, symbol) in value.iter().enumerate() {
+                if is_color(value, i) && !colors.contains(symbol) {
+                    colors.push(*symbol);
+                }
+            }
+        }
+        for symbol in symbols {
+            let bracket = matches!(symbol, TurtleSymbol::PushStack | TurtleSymbol::PopStack);
+            if !bracket && !colors.contains(symbol) && !alphabet.contains(symbol) {
+                alphabet.push(*symbol);
+            }
+        }
+        alphabet.sort_by_key(|symbol| symbol.to_string());
+        alphabet
+    }
+
+    /// A copy of the grammar with one random change: a symbol of a rule inserted,
+    /// deleted or replaced, a branch added to a rule or the angle moved by one
+    /// division. Grammars without rules change their axiom instead.
+    pub fn mutate(&self, random: &mut Random) -> TurtleGraphConfig {
+        let mut config = self.clone();
+        if random.below(5) == 0 {
+            let angle = config.angle.unwrap_or(*ANGLES.start());
+            let angle = match random.below(2) {
+                0 => angle.saturating_sub(1),
+                _ => angle.saturating_add(1),
+            };
+            config.angle = Some(angle.clamp(*ANGLES.start(), *ANGLES.end()));
+            return config;
+        }
+        let alphabet = self.alphabet();
+        let mut symbols: Vec<TurtleSymbol> = self.rules.keys().copied().collect();
+        symbols.sort_by_key(|symbol| symbol.to_string());
+        let value = match symbols.get(random.below(symbols.len().max(1))) {
+            Some(symbol) => config
+                .rules
+                .get_mut(symbol)
+                .expect("symbols come from the rules"),
+            None => &mut config.axiom,
+        };
+        mutate_production(value, &alphabet, random);
+        config
+    }
+
+    /// A grammar taking the angle and each rule from one of the parents at random,
+    /// a rule of a single parent may be left out.
+    pub fn breed(&self, other: &TurtleGraphConfig, random: &mut Random) -> TurtleGraphConfig {
+        let mut child = self.clone();
+        if random.below(2) == 1 {
+            child.angle = other.angle;
+        }
+        let mut symbols: Vec<TurtleSymbol> = self
+            .rules
+            .keys()
+            .chain(other.rules.keys())
+            .copied()
+            .collect();
+        symbols.sort_by_key(|symbol| symbol.to_string());
+        symbols.dedup();
+        for symbol in symbols {
+            let parent = if random.below(2) == 0 { self } else { other };
+            match parent.rules.get(&symbol) {
+                Some(value) => child.rules.insert(symbol, value.clone()),
+                None => child.rules.remove(&symbol),
+            };
+        }
+        child
+    }
+}
+
+/// An interactive session breeding variants of a grammar, drawing each generation
+/// on a contact sheet and taking the parents of the next one from the user.
+#[derive(Debug)]
+pub struct Evolution {
+    parents: Vec<TurtleGraphConfig>,
+    variants: Vec<TurtleGraphConfig>,
+    generation: usize,
+    count: usize,
+    random: Random,
+    /// Where the contact sheets and the saved grammars are written.
+    directory: PathBuf,
+}
+
+impl Evolution {
+    pub fn new(
+        seed: TurtleGraphConfig,
+        count: usize,
+        random_seed: u64,
+        directory: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            parents: vec![seed],
+            variants: Vec::new(),
+            generation: 0,
+            count,
+            random: Random::new(random_seed),
+            directory: directory.into(),
+        }
+    }
+
+    pub fn variants(&self) -> &[TurtleGraphConfig] {
+        &self.variants
+    }
+
+    /// Breeds a variant from two parents, which may be the same one, and mutates it.
+    fn variant(&mut self) -> TurtleGraphConfig {
+        let first = &self.parents[self.random.below(self.parents.len())];
+        let second = &self.parents[self.random.below(self.parents.len())];
+        first
+            .breed(second, &mut self.random)
+            .mutate(&mut self.random)
+    }
+
+    /// Every variant of the generation as a numbered thumbnail, in rows of
+    /// `SHEET_COLUMNS`.
+    pub fn contact_sheet(&self) -> String {
+        let cell = THUMBNAIL_SIZE + SHEET_MARGIN;
+        let rows = self.variants.len().div_ceil(SHEET_COLUMNS).max(1) as u32;
+        let mut value = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            SHEET_COLUMNS as u32 * cell,
+            rows * cell
+        );
+        for (i, variant) in self.variants.iter().enumerate() {
+            let x = (i % SHEET_COLUMNS) as u32 * cell;
+            let y = (i / SHEET_COLUMNS) as u32 * cell;
+            value.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"14\">{}</text>\n",
+                x + SHEET_MARGIN / 2,
+                y + SHEET_MARGIN * 3 / 4,
+                i + 1
+            ));
+            value.push_str(&variant.thumbnail_syntax().svg_thumbnail(
+                x + SHEET_MARGIN / 2,
+                y + SHEET_MARGIN,
+                THUMBNAIL_SIZE - SHEET_MARGIN / 2,
+            ));
+            value.push('\n');
+        }
+        value.push_str("</svg>\n");
+        value
+    }
+
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        writeln!(output, "{}", HELP)?;
+        self.next_generation(output)?;
+        for line in input.lines() {
+            if !self.command(&line?, output)? {
+                break;
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Breeds the variants of the generation, writes their contact sheet and lists
+    /// their rules.
+    fn next_generation(&mut self, output: &mut impl Write) -> io::Result<()> {
+        self.variants = (0..self.count).map(|_| self.variant()).collect();
+        let path = self
+            .directory
+            .join(format!("generation_{}.svg", self.generation));
+        std::fs::write(&path, self.contact_sheet())?;
+        writeln!(output, "generation {}: {}", self.generation, path.display())?;
+        for (i, variant) in self.variants.iter().enumerate() {
+            let rules: Vec<String> = variant
+                .sorted_rules()
+                .iter()
+                .map(|(symbol, value)| format!("{} = {}", symbol, symbols_to_string(value)))
+                .collect();
+            writeln!(
+                output,
+                "{:>3}: angle {}, {}",
+                i + 1,
+                variant.angle.unwrap_or(0),
+                rules.join(", ")
+            )?;
+        }
+        write!(output, "> ")?;
+        output.flush()
+    }
+
+    /// The variants picked by their numbers on `words`.
+    fn picked(&self, words: &str) -> Result<Vec<TurtleGraphConfig>, String> {
+        words
+            .split_whitespace()
+            .map(|word| match word.parse::<usize>() {
+                Ok(number) if (1..=self.variants.len()).contains(&number) => {
+                    Ok(self.variants[number - 1].clone())
+                }
+                _ => Err(format!(
+                    "`{}` is not the number of a variant, from 1 to {}",
+                    word,
+                    self.variants.len()
+                )),
+            })
+            .collect()
+    }
+
+    /// Runs a single command, returning `false` when the session is over.
+    pub fn command(&mut self, line: &str, output: &mut impl Write) -> io::Result<bool> {
+        let line = line.trim();
+        let (command, argument) = match line.find(' ') {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        match (command, argument) {
+            ("", _) => {}
+            ("quit", "") | ("exit", "") => return Ok(false),
+            ("help", "") => writeln!(output, "{}", HELP)?,
+            ("save", numbers) if !numbers.is_empty() => match self.picked(numbers) {
+                Ok(picked) => {
+                    for (number, variant) in numbers.split_whitespace().zip(picked) {
+                        let path = self
+                            .directory
+                            .join(format!("generation_{}_{}.lsy", self.generation, number));
+                        std::fs::write(&path, variant.to_string())?;
+                        writeln!(output, "saved {}", path.display())?;
+                    }
+                }
+                Err(error) => writeln!(output, "{}", error)?,
+            },
+            _ => match self.picked(line) {
+                Ok(parents) => {
+                    self.parents = parents;
+                    self.generation += 1;
+                    self.next_generation(output)?;
+                    return Ok(true);
+                }
+                Err(error) => writeln!(output, "{}", error)?,
+            },
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::temp::TempDir;
+
+    const PLANT: &str = "angle 7\norder 3\naxiom X\nF = FF\nX = C4F[+X]F[-X]+C5X\n";
+
+    fn balanced(value: &[TurtleSymbol]) -> bool {
+        let mut depth = 0i32;
+        for symbol in value {
+            match symbol {
+                TurtleSymbol::PushStack => depth += 1,
+                TurtleSymbol::PopStack => depth -= 1,
+                _ => {}
+            }
+            if depth < 0 {
+                return false;
+            }
+        }
+        depth == 0
+    }
+
+    #[test]
+    fn mutations() {
+        let mut config = TurtleGraphConfig::from_str(PLANT).unwrap();
+        let mut random = Random::new(3);
+        let mut angles = std::collections::HashSet::new();
+        for _ in 0..300 {
+            let mutated = config.mutate(&mut random);
+            assert!(mutated.rules.values().all(|value| balanced(value)));
+            let colors = mutated.rules.values().all(|value| {
+                let string = symbols_to_string(value);
+                string.matches('C').count()
+                    == string.matches("C4").count() + string.matches("C5").count()
+            });
+            assert!(colors, "{}", mutated);
+            assert!(ANGLES.contains(&mutated.angle.unwrap()));
+            assert_eq!(
+                mutated.to_string().parse::<TurtleGraphConfig>(),
+                Ok(mutated.clone())
+            );
+            angles.insert(mutated.angle);
+            config = mutated;
+        }
+        assert!(angles.len() > 1);
+        let value = crate::parse_symbols("F[+F[-F]]]").unwrap();
+        assert_eq!(partner(&value, 1), Some(8));
+        assert_eq!(partner(&value, 8), Some(1));
+        assert_eq!(partner(&value, 9), None);
+        assert_eq!(partner(&value, 0), None);
+    }
+
+    #[test]
+    fn breeding() {
+        let first = TurtleGraphConfig::from_str(PLANT).unwrap();
+        let second = TurtleGraphConfig::from_str("angle 5\naxiom X\nX = FX\nY = F").unwrap();
+        let mut random = Random::new(1);
+        let children: Vec<TurtleGraphConfig> =
+            (0..20).map(|_| first.breed(&second, &mut random)).collect();
+        let rules =
+            |config: &TurtleGraphConfig| symbols_to_string(&config.rules[&TurtleSymbol::from('X')]);
+        assert!(children.iter().any(|child| rules(child) == "FX"));
+        assert!(children
+            .iter()
+            .any(|child| rules(child) == "C4F[+X]F[-X]+C5X"));
+        assert!(children.iter().any(|child| child.angle == Some(5)));
+        assert!(children.iter().all(|child| child.axiom == first.axiom));
+    }
+
+    #[test]
+    fn session() {
+        let temp = TempDir::new("evolve");
+        let directory = temp.path();
+        let config = TurtleGraphConfig::from_str(PLANT).unwrap();
+        let mut evolution = Evolution::new(config, 6, 1, directory);
+        let mut output = Vec::new();
+        let input = "2 5\n7\nsave 3\nquit\n1\n";
+        evolution.run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("generation 1: "));
+        assert!(output.contains("`7` is not the number of a variant, from 1 to 6\n"));
+        assert!(!output.contains("generation 2: "));
+        assert_eq!(evolution.variants().len(), 6);
+        assert!(evolution
+            .variants()
+            .iter()
+            .all(|variant| variant.order == Some(3)));
+        let sheet = std::fs::read_to_string(directory.join("generation_1.svg")).unwrap();
+        assert_eq!(sheet.matches("<svg x=").count(), 6);
+        let saved = std::fs::read_to_string(directory.join("generation_1_3.lsy")).unwrap();
+        assert_eq!(
+            TurtleGraphConfig::from_str(&saved).unwrap(),
+            evolution.variants()[2]
+        );
+    }
+}
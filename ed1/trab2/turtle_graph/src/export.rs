@@ -0,0 +1,199 @@
+use crate::{bounds, optimize, Colors, Path, Point, TurtleSyntax};
+
+/// Width in centimeters of the larger side of a TikZ picture.
+const TIKZ_SIZE: f32 = 10.0;
+const CANVAS_SIZE: u32 = 800;
+
+fn color_name(color: Colors) -> String {
+    format!("turtle{:?}", color)
+}
+
+impl TurtleSyntax {
+    /// Optimised paths with the `rotate` of the grammar already applied.
+    pub fn paths(&self) -> Vec<Path> {
+        let mut paths = optimize(&self.segments());
+        if self.rotate != 0.0 {
+            for path in paths.iter_mut() {
+                for point in path.points.iter_mut() {
+                    *point = point.rotated(self.rotate);
+                }
+            }
+        }
+        paths
+    }
+
+    /// A `tikzpicture` with one `\draw` per path, scaled so the larger side has
+    /// `TIKZ_SIZE` centimeters since TeX dimensions overflow on big drawings.
+    pub fn convert_tikz(&self) -> String {
+        let paths = self.paths();
+        let mut value = String::from("% requires \\usepackage{tikz}\n\\begin{tikzpicture}\n");
+        let mut colors: Vec<Colors> = Vec::new();
+        for path in &paths {
+            if !colors.contains(&path.color) {
+                colors.push(path.color);
+                let (r, g, b) = path.color.rgb();
+                value.push_str(&format!(
+                    "\\definecolor{{{}}}{{rgb}}{{{},{},{}}}\n",
+                    color_name(path.color),
+                    r,
+                    g,
+                    b
+                ));
+            }
+        }
+        if let Some((min, max)) = bounds(&paths) {
+            let scale = TIKZ_SIZE / (max.x - min.x).max(max.y - min.y).max(f32::EPSILON);
+            let scaled = |point: &Point| {
+                format!(
+                    "({:.3},{:.3})",
+                    (point.x - min.x) * scale,
+                    (point.y - min.y) * scale
+                )
+            };
+            for path in &paths {
+                value.push_str(&format!("\\draw[{}] ", color_name(path.color)));
+                for (i, point) in path.points.iter().enumerate() {
+                    if i > 0 {
+                        value.push_str(if i % 6 == 0 { "\n  -- " } else { " -- " });
+                    }
+                    value.push_str(&scaled(point));
+                }
+                value.push_str(";\n");
+            }
+        }
+        value.push_str("\\end{tikzpicture}\n");
+        value
+    }
+
+    /// A standalone page drawing the paths on a `<canvas>`.
+    pub fn convert_html(&self) -> String {
+        let paths = self.paths();
+        let (min, max) = bounds(&paths).unwrap_or((Point::new(0.0, 0.0), Point::new(0.0, 0.0)));
+        let mut value = format!(
+            include_str!("preamble/canvas_header.html"),
+            size = CANVAS_SIZE
+        );
+        value.push_str(&format!(
+            "const bounds = [{:.2}, {:.2}, {:.2}, {:.2}];\nconst paths = [\n",
+            min.x, min.y, max.x, max.y
+        ));
+        for path in &paths {
+            let (r, g, b) = path.color.rgb();
+            let points: Vec<String> = path
+                .points
+                .iter()
+                .map(|point| format!("{:.2},{:.2}", point.x, point.y))
+                .collect();
+            value.push_str(&format!(
+                "[\"rgb({},{},{})\", [{}]],\n",
+                (r * 255.0).round(),
+                (g * 255.0).round(),
+                (b * 255.0).round(),
+                points.join(",")
+            ));
+        }
+        value.push_str("];\n");
+        value.push_str(include_str!("preamble/canvas_content.html"));
+        value
+    }
+
+    /// A standalone SVG with one `<path>` per path, the y axis is flipped so the
+    /// drawing has the same orientation as the PostScript output.
+    pub fn convert_svg(&self) -> String {
+        self.svg(
+            &format!(
+                "xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\"",
+                size = CANVAS_SIZE
+            ),
+            "\n",
+        )
+    }
+
+    /// The drawing as an `<svg>` element of `size` pixels placed at `x`, `y` of the
+    /// SVG containing it, for sheets of several drawings.
+    pub fn svg_thumbnail(&self, x: u32, y: u32, size: u32) -> String {
+        self.svg(
+            &format!(
+                "x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\"",
+                x,
+                y,
+                size = size
+            ),
+            "",
+        )
+    }
+
+    /// An `<svg>` element with `attributes` fitting every path in its view box.
+    fn svg(&self, attributes: &str, end: &str) -> String {
+        let paths = self.paths();
+        let (min, max) = bounds(&paths).unwrap_or((Point::new(0.0, 0.0), Point::new(0.0, 0.0)));
+        let mut value = format!(
+            "<svg {} viewBox=\"{:.2} {:.2} {:.2} {:.2}\">\n<g fill=\"none\" stroke-width=\"1\">\n",
+            attributes,
+            min.x,
+            -max.y,
+            (max.x - min.x).max(f32::EPSILON),
+            (max.y - min.y).max(f32::EPSILON),
+        );
+        for path in &paths {
+            let (r, g, b) = path.color.rgb();
+            let points: Vec<String> = path
+                .points
+                .iter()
+                .map(|point| format!("{:.2} {:.2}", point.x, -point.y))
+                .collect();
+            value.push_str(&format!(
+                "<path stroke=\"rgb({},{},{})\" vector-effect=\"non-scaling-stroke\" d=\"M {}\"/>\n",
+                (r * 255.0).round(),
+                (g * 255.0).round(),
+                (b * 255.0).round(),
+                points.join(" L ")
+            ));
+        }
+        value.push_str("</g>\n</svg>");
+        value.push_str(end);
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::TurtleGraphConfig;
+
+    const SQUARE: &str = "angle 4\norder 1\naxiom F+F+C1F+F\n";
+
+    #[test]
+    fn tikz() {
+        let syntax = TurtleGraphConfig::from_str(SQUARE)
+            .unwrap()
+            .generate_syntax();
+        let value = syntax.convert_tikz();
+        assert!(value.contains("\\definecolor{turtleRed}{rgb}{1,0,0}\n"));
+        assert!(value
+            .contains("\\draw[turtleBlack] (0.000,0.000) -- (10.000,0.000) -- (10.000,10.000);\n"));
+        assert!(value.contains("\\draw[turtleRed] (10.000,10.000) -- (0.000,10.000)"));
+    }
+
+    #[test]
+    fn html() {
+        let syntax = TurtleGraphConfig::from_str(SQUARE)
+            .unwrap()
+            .generate_syntax();
+        let value = syntax.convert_html();
+        assert!(value.contains("<canvas id=\"turtle\" width=\"800\" height=\"800\">"));
+        assert!(value.contains("const bounds = [-0.00, -0.00, 100.00, 100.00];"));
+        assert!(value.contains("[\"rgb(0,0,0)\", [0.00,0.00,100.00,0.00,100.00,100.00]],"));
+    }
+
+    #[test]
+    fn svg() {
+        let syntax = TurtleGraphConfig::from_str(SQUARE)
+            .unwrap()
+            .generate_syntax();
+        let value = syntax.convert_svg();
+        assert!(value.contains("viewBox=\"-0.00 -100.00 100.00 100.00\""));
+        assert!(value.contains("d=\"M 0.00 -0.00 L 100.00 -0.00 L 100.00 -100.00\"/>"));
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use crate::TurtleSyntax;
+
+/// Most symbols a golden drawing is made from, grammars growing past it are
+/// checked on the last generation under it so the files stay small.
+pub const GOLDEN_SYMBOLS: u64 = 5_000;
+
+/// Two decimals, without the sign of a rounded zero.
+fn rounded(value: f32) -> String {
+    let value = format!("{:.2}", value);
+    match value.as_str() {
+        "-0.00" => "0.00".into(),
+        _ => value,
+    }
+}
+
+impl TurtleSyntax {
+    /// What the syntax draws, one segment per line as `x0 y0 x1 y1 Color` with
+    /// the points rotated and rounded to two decimals. Changes to the expansion or
+    /// the walk that do not move the drawing leave it the same.
+    pub fn geometry(&self) -> String {
+        let mut value = String::new();
+        for segment in self.walk().segments {
+            let (from, to) = (
+                segment.from.rotated(self.rotate),
+                segment.to.rotated(self.rotate),
+            );
+            writeln!(
+                value,
+                "{} {} {} {} {:?}",
+                rounded(from.x),
+                rounded(from.y),
+                rounded(to.x),
+                rounded(to.y),
+                segment.color
+            )
+            .unwrap();
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, str::FromStr};
+
+    use super::*;
+    use crate::TurtleGraphConfig;
+
+    #[test]
+    fn geometry() {
+        let config = TurtleGraphConfig::from_str("angle 4\nrotate 90\naxiom C1F-F").unwrap();
+        assert_eq!(
+            config.generate_syntax().geometry(),
+            "0.00 0.00 0.00 100.00 Red\n0.00 100.00 100.00 100.00 Red\n"
+        );
+    }
+
+    /// Header of a golden file, so a grammar checked on fewer generations says so.
+    fn golden(name: &str, config: &TurtleGraphConfig) -> String {
+        let config = config.truncated(GOLDEN_SYMBOLS);
+        format!(
+            "; {} order {}\n{}",
+            name,
+            config.order.unwrap_or(0),
+            config.generate_syntax().geometry()
+        )
+    }
+
+    /// Draws every grammar of `grammar/` and compares it with its file on
+    /// `golden/`. `BLESS=1 cargo test golden` writes the files again.
+    #[test]
+    fn grammars() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let directory = root.join("golden");
+        let bless = std::env::var("BLESS").is_ok_and(|bless| bless == "1");
+        let mut paths: Vec<_> = std::fs::read_dir(root.join("grammar"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "lsy"))
+            .collect();
+        paths.sort();
+
+        let mut failures = Vec::new();
+        for path in &paths {
+            let name = path.file_stem().unwrap().to_string_lossy();
+            let source = std::fs::read_to_string(path).unwrap();
+            let config = TurtleGraphConfig::from_str(&source).unwrap();
+            let value = golden(&name, &config);
+            let golden_path = directory.join(format!("{}.txt", name));
+            if bless {
+                std::fs::create_dir_all(&directory).unwrap();
+                std::fs::write(&golden_path, value).unwrap();
+                continue;
+            }
+            let expected = match std::fs::read_to_string(&golden_path) {
+                Ok(expected) => expected,
+                Err(_) => {
+                    failures.push(format!("{}: no golden file", golden_path.display()));
+                    continue;
+                }
+            };
+            if let Some((line, (expected, found))) = expected
+                .lines()
+                .chain(std::iter::repeat("<end>"))
+                .zip(value.lines().chain(std::iter::repeat("<end>")))
+                .take(expected.lines().count().max(value.lines().count()))
+                .enumerate()
+                .find(|(_, (expected, found))| expected != found)
+            {
+                failures.push(format!(
+                    "{}:{}: expected `{}`, found `{}`",
+                    golden_path.display(),
+                    line + 1,
+                    expected,
+                    found
+                ));
+            }
+        }
+        for entry in std::fs::read_dir(&directory).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            if !paths
+                .iter()
+                .any(|grammar| grammar.file_stem().unwrap() == name.as_str())
+            {
+                failures.push(format!("{}: no grammar draws it", path.display()));
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "{}\nrun `BLESS=1 cargo test golden` if the changes are right",
+            failures.join("\n")
+        );
+    }
+}
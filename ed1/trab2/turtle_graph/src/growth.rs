@@ -0,0 +1,351 @@
+use std::collections::HashSet;
+
+use crate::{big::BigUint, symbols_to_string, TurtleGraphConfig, TurtleSymbol, Variable};
+
+/// Default number of symbols above which `growth` warns about the order.
+pub const GROWTH_LIMIT: u64 = 10_000_000;
+
+/// Generations run by the power iteration estimating the growth rate, the last
+/// `RATE_SAMPLES` of them are averaged so periodic matrices still converge.
+const RATE_ITERATIONS: usize = 512;
+const RATE_SAMPLES: usize = 64;
+
+/// Rough size of a point of a merged PostScript path, ` 123.45 678.90 l`.
+const POSTSCRIPT_POINT_SIZE: usize = 17;
+
+/// Symbol counts of every generation, taken from the production matrix without
+/// expanding the string.
+#[derive(Debug)]
+pub struct Growth {
+    /// Symbols reachable from the axiom, in the order of the columns.
+    pub symbols: Vec<TurtleSymbol>,
+    /// Segments each symbol draws once interpreted, in the order of `symbols`.
+    pub strokes: Vec<u32>,
+    /// Bytes each symbol takes on the syntax file once interpreted.
+    pub bytes: Vec<u32>,
+    /// Count of each symbol on each generation, from the axiom up to the order.
+    pub generations: Vec<Vec<BigUint>>,
+    /// Asymptotic ratio between the sizes of two generations.
+    pub rate: f64,
+    /// Whether conditions on the age of symbols or on where the turtle reached a `?`
+    /// were left out, the counts then only follow the other rules.
+    pub conditions_ignored: bool,
+    /// Whether branches removed by `%` or for leaving the region were left out,
+    /// the counts are then upper bounds.
+    pub pruning_ignored: bool,
+}
+
+impl TurtleGraphConfig {
+    /// Symbols reachable from the axiom, sorted like the rules on `.lsy` files.
+    pub(crate) fn reachable_symbols(&self) -> Vec<TurtleSymbol> {
+        let mut reachable: HashSet<TurtleSymbol> = self.axiom.iter().copied().collect();
+        let mut queue: Vec<TurtleSymbol> = reachable.iter().copied().collect();
+        while let Some(symbol) = queue.pop() {
+            let conditional = self
+                .conditional_rules
+                .iter()
+                .filter(|rule| rule.symbol == symbol)
+                .map(|rule| &rule.value);
+            for next in self
+                .rules
+                .get(&symbol)
+                .into_iter()
+                .chain(conditional)
+                .flatten()
+            {
+                if reachable.insert(*next) {
+                    queue.push(*next);
+                }
+            }
+        }
+        let mut symbols: Vec<TurtleSymbol> = reachable.into_iter().collect();
+        symbols.sort_by_key(|symbol| symbol.to_string());
+        symbols
+    }
+
+    /// `matrix[i][j]` is how many times the symbol `j` appears after rewriting `i`
+    /// on step `time`, symbols without a rule rewrite to themselves. Conditions on
+    /// the age can not be told apart here and only hold for new symbols, the ones
+    /// on the position never hold.
+    fn production_matrix(&self, symbols: &[TurtleSymbol], time: usize) -> Vec<Vec<u32>> {
+        symbols
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| {
+                let mut row = vec![0; symbols.len()];
+                match self.production(symbol, time, 0, None) {
+                    Some(value) => {
+                        for next in value {
+                            if let Some(j) = symbols.iter().position(|s| s == next) {
+                                row[j] += 1;
+                            }
+                        }
+                    }
+                    None => row[i] = 1,
+                }
+                row
+            })
+            .collect()
+    }
+
+    /// A copy of the grammar drawing the last generation with at most `limit`
+    /// symbols, when its order has more.
+    pub fn truncated(&self, limit: u64) -> TurtleGraphConfig {
+        let mut config = self.clone();
+        let largest = self.growth().largest_under(limit).unwrap_or(0);
+        config.order = config.order.map(|order| order.min(largest as u8));
+        config
+    }
+
+    /// Counts the symbols of every generation up to the order of the grammar.
+    pub fn growth(&self) -> Growth {
+        let symbols = self.reachable_symbols();
+        let conditions_ignored = self
+            .conditional_rules
+            .iter()
+            .any(|rule| rule.condition.variable == Variable::Age || rule.condition.is_position());
+        let axiom: Vec<u64> = symbols
+            .iter()
+            .map(|symbol| self.axiom.iter().filter(|s| *s == symbol).count() as u64)
+            .collect();
+
+        let mut generations = vec![axiom.iter().map(|c| BigUint::from(*c)).collect()];
+        for time in 0..self.order.unwrap_or(0) as usize {
+            let matrix = self.production_matrix(&symbols, time);
+            let last: &Vec<BigUint> = &generations[generations.len() - 1];
+            let mut next = vec![BigUint::default(); symbols.len()];
+            for (i, count) in last.iter().enumerate() {
+                for (j, times) in matrix[i].iter().enumerate() {
+                    if *times > 0 && !count.is_zero() {
+                        next[j] = next[j].add(&count.mul_small(*times));
+                    }
+                }
+            }
+            generations.push(next);
+        }
+
+        // Long after every condition on time changed for the last time.
+        let matrix = self.production_matrix(&symbols, usize::MAX);
+        let mut vector: Vec<f64> = axiom.iter().map(|c| *c as f64).collect();
+        let mut logs = Vec::with_capacity(RATE_ITERATIONS);
+        for _ in 0..RATE_ITERATIONS {
+            let mut next = vec![0f64; symbols.len()];
+            for (i, count) in vector.iter().enumerate() {
+                for (j, times) in matrix[i].iter().enumerate() {
+                    next[j] += count * *times as f64;
+                }
+            }
+            let total: f64 = next.iter().sum();
+            if total == 0.0 {
+                break;
+            }
+            logs.push(total.ln());
+            vector = next.iter().map(|c| c / total).collect();
+        }
+        let samples = &logs[logs.len().saturating_sub(RATE_SAMPLES)..];
+        let rate = if samples.is_empty() {
+            0.0
+        } else {
+            (samples.iter().sum::<f64>() / samples.len() as f64).exp()
+        };
+
+        let interpreted: Vec<&[TurtleSymbol]> = symbols
+            .iter()
+            .map(|symbol| match self.interpretations.get(symbol) {
+                Some(value) => value.as_slice(),
+                None => std::slice::from_ref(symbol),
+            })
+            .collect();
+        let strokes = interpreted
+            .iter()
+            .map(|value| {
+                value
+                    .iter()
+                    .filter(|symbol| symbol.command(&self.draws) == TurtleSymbol::F)
+                    .count() as u32
+            })
+            .collect();
+        let bytes = interpreted
+            .iter()
+            .map(|value| symbols_to_string(value).len() as u32)
+            .collect();
+        Growth {
+            symbols,
+            strokes,
+            bytes,
+            generations,
+            rate,
+            conditions_ignored,
+            pruning_ignored: self.prunes(),
+        }
+    }
+}
+
+impl Growth {
+    pub fn total(&self, generation: usize) -> BigUint {
+        self.generations[generation]
+            .iter()
+            .fold(BigUint::default(), |total, count| total.add(count))
+    }
+
+    /// Every `F`, or symbol drawn like it, draws one segment.
+    pub fn segments(&self, generation: usize) -> BigUint {
+        self.weighted(generation, &self.strokes)
+    }
+
+    /// Bytes of the syntax file, which holds the expanded string.
+    pub fn syntax_size(&self, generation: usize) -> BigUint {
+        self.weighted(generation, &self.bytes)
+    }
+
+    /// Sum of the counts of `generation` times the weight of each symbol.
+    fn weighted(&self, generation: usize, weights: &[u32]) -> BigUint {
+        weights
+            .iter()
+            .zip(&self.generations[generation])
+            .fold(BigUint::default(), |total, (weight, count)| {
+                total.add(&count.mul_small(*weight))
+            })
+    }
+
+    /// Estimated bytes of the PostScript file, assuming segments are chained into
+    /// paths.
+    pub fn postscript_size(&self, generation: usize) -> BigUint {
+        let preamble = include_str!("preamble/preamble_header.txt").len()
+            + include_str!("preamble/preamble_content.txt").len();
+        self.segments(generation)
+            .mul_small(POSTSCRIPT_POINT_SIZE as u32)
+            .add(&BigUint::from(preamble as u64))
+    }
+
+    /// The last generation with at most `limit` symbols.
+    pub fn largest_under(&self, limit: u64) -> Option<usize> {
+        let limit = BigUint::from(limit);
+        (0..self.generations.len())
+            .rev()
+            .find(|generation| self.total(*generation) <= limit)
+    }
+
+    /// Table of the counts followed by the estimates of the last generation, with
+    /// a warning when it has more than `limit` symbols.
+    pub fn report(&self, limit: u64) -> String {
+        let mut rows = vec![vec!["generation".to_string(), "symbols".to_string()]];
+        rows[0].extend(self.symbols.iter().map(|s| symbols_to_string(&[*s])));
+        for (generation, counts) in self.generations.iter().enumerate() {
+            let mut row = vec![generation.to_string(), self.total(generation).to_string()];
+            row.extend(counts.iter().map(|c| c.to_string()));
+            rows.push(row);
+        }
+        let widths: Vec<usize> = (0..rows[0].len())
+            .map(|i| rows.iter().map(|row| row[i].len()).max().unwrap_or(0))
+            .collect();
+        let mut value = String::new();
+        for row in &rows {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>1$}", cell, width))
+                .collect();
+            value.push_str(&cells.join("  "));
+            value.push('\n');
+        }
+
+        let order = self.generations.len() - 1;
+        if self.conditions_ignored {
+            value.push_str(
+                "warning: conditions on the age of symbols or the position of `?` are left out\n",
+            );
+        }
+        if self.pruning_ignored {
+            value.push_str(
+                "warning: branches cut by `%` or the region are counted as if they grew\n",
+            );
+        }
+        value.push_str(&format!("growth rate: {:.4}\n", self.rate));
+        value.push_str(&format!("segments: {}\n", self.segments(order)));
+        value.push_str(&format!("syntax file: {} bytes\n", self.syntax_size(order)));
+        value.push_str(&format!(
+            "PostScript file: about {} bytes\n",
+            self.postscript_size(order)
+        ));
+        if self.total(order) > BigUint::from(limit) {
+            let largest = self.largest_under(limit);
+            value.push_str(&format!(
+                "warning: order {} has more than {} symbols",
+                order, limit
+            ));
+            match largest {
+                Some(largest) => {
+                    value.push_str(&format!(", order {} is the largest under it\n", largest))
+                }
+                None => value.push('\n'),
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn koch() {
+        let config =
+            TurtleGraphConfig::from_str("angle 6\norder 3\naxiom F--F--F\nF = F+F--F+F\nX = F")
+                .unwrap();
+        let growth = config.growth();
+        assert_eq!(growth.symbols.len(), 3);
+        assert_eq!(growth.segments(3).to_string(), "192");
+        assert_eq!(
+            growth.total(3).to_string(),
+            config.generate_syntax().string().len().to_string()
+        );
+        assert_eq!(growth.syntax_size(3), growth.total(3));
+        assert!((growth.rate - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn empty_rule() {
+        let config =
+            TurtleGraphConfig::from_str("angle 4\norder 4\naxiom FXF\nX = \nF = F+XF").unwrap();
+        let growth = config.growth();
+        let expanded = config.derive_syntax().list.iter().count();
+        assert_eq!(growth.total(4).to_string(), expanded.to_string());
+        assert_eq!(growth.segments(4).to_string(), "32");
+    }
+
+    #[test]
+    fn fibonacci() {
+        let config = TurtleGraphConfig::from_str("order 90\naxiom A\nA = AB\nB = A").unwrap();
+        let growth = config.growth();
+        assert_eq!(growth.total(90).to_string(), "7540113804746346429");
+        assert!((growth.rate - (1.0 + 5f64.sqrt()) / 2.0).abs() < 1e-6);
+        let report = growth.report(1000);
+        assert!(report.contains("warning: order 90 has more than 1000 symbols, order 14 is"));
+        assert!(report.starts_with("generation"));
+        assert_eq!(growth.largest_under(1000), Some(14));
+        assert_eq!(growth.largest_under(0), None);
+    }
+
+    #[test]
+    fn phases() {
+        let config = TurtleGraphConfig::from_str(
+            "order 5
+axiom F
+F : t < 2 = FF
+F : t >= 2 = F[F]",
+        )
+        .unwrap();
+        let growth = config.growth();
+        assert_eq!(growth.segments(5).to_string(), "32");
+        assert_eq!(
+            growth.total(5).to_string(),
+            config.generate_syntax().string().len().to_string()
+        );
+        assert!((growth.rate - 2.0).abs() < 1e-6);
+        assert!(!growth.conditions_ignored && !growth.pruning_ignored);
+    }
+}
@@ -0,0 +1,377 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::Path,
+};
+
+use crate::{Colors, Entry, TurtleGraphConfig, TurtleSymbol};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+fn entry_name(entry: &Entry) -> String {
+    match entry {
+        Entry::Angle => "angle".into(),
+        Entry::Order => "order".into(),
+        Entry::Rotate => "rotate".into(),
+        Entry::AngleJitter => "angle_jitter".into(),
+        Entry::LengthJitter => "length_jitter".into(),
+        Entry::Seed => "seed".into(),
+        Entry::Tropism => "tropism".into(),
+        Entry::Region => "region".into(),
+        Entry::Draw(symbol) => format!("draw for `{}`", symbol),
+        Entry::Axiom => "axiom".into(),
+        Entry::Rule(symbol) => format!("rule for `{}`", symbol),
+        Entry::ConditionalRule(_) => "conditional rule".into(),
+        Entry::Interpret(symbol) => format!("interpretation of `{}`", symbol),
+        Entry::End => "end".into(),
+    }
+}
+
+/// Brackets of a single axiom or production, `]` without a `[` would pop an empty
+/// stack which the turtle silently ignores.
+fn check_brackets(symbols: &[TurtleSymbol], name: &str) -> Option<String> {
+    let mut depth = 0usize;
+    for (i, symbol) in symbols.iter().enumerate() {
+        match symbol {
+            TurtleSymbol::PushStack => depth += 1,
+            TurtleSymbol::PopStack if depth == 0 => {
+                return Some(format!(
+                    "`]` at position {} of the {} has no matching `[`",
+                    i + 1,
+                    name
+                ))
+            }
+            TurtleSymbol::PopStack => depth -= 1,
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        Some(format!("{} `[` of the {} are never closed", depth, name))
+    } else {
+        None
+    }
+}
+
+/// Checks the color digits after every `C`, returning the positions of the digits so
+/// they are not reported as symbols without meaning.
+fn check_colors(
+    symbols: &[TurtleSymbol],
+    name: &str,
+    messages: &mut Vec<String>,
+) -> HashSet<usize> {
+    let mut digits = HashSet::new();
+    for (i, symbol) in symbols.iter().enumerate() {
+        if *symbol != TurtleSymbol::CustomSymbol('C') {
+            continue;
+        }
+        match symbols.get(i + 1) {
+            Some(TurtleSymbol::CustomSymbol(c)) if Colors::from_digit(*c).is_some() => {
+                digits.insert(i + 1);
+            }
+            Some(symbol) => messages.push(format!(
+                "`C` at position {} of the {} is followed by `{}` which is not a color from 0 to 6",
+                i + 1,
+                name,
+                symbol
+            )),
+            None => messages.push(format!(
+                "`C` ends the {}, its color is taken from whatever follows it",
+                name
+            )),
+        }
+    }
+    digits
+}
+
+/// Reports mistakes of a grammar that still parses but likely draws something other
+/// than intended, `directory` being where it was read from.
+pub fn lint(text: &str, directory: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut config = TurtleGraphConfig {
+        directory: directory.into(),
+        ..TurtleGraphConfig::default()
+    };
+    let mut lines: HashMap<Entry, usize> = HashMap::new();
+    for (i, line) in text.lines().enumerate() {
+        match config.parse_line(line, i + 1) {
+            Ok(Some(entry)) => {
+                if let Some(previous) = lines.insert(entry, i + 1) {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        line: Some(i + 1),
+                        message: format!(
+                            "{} is defined again, replacing the one of line {}",
+                            entry_name(&entry),
+                            previous
+                        ),
+                    });
+                }
+            }
+            Ok(None) => {}
+            Err(error) => diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                line: Some(error.line),
+                message: error.message,
+            }),
+        }
+    }
+
+    let mut report = |severity, entry: Entry, message| {
+        diagnostics.push(Diagnostic {
+            severity,
+            line: lines.get(&entry).copied(),
+            message,
+        })
+    };
+    if config.axiom.is_empty() {
+        report(
+            Severity::Warning,
+            Entry::Axiom,
+            "there is no axiom, nothing is drawn".into(),
+        );
+    }
+    let turns = config
+        .rules
+        .values()
+        .chain(std::iter::once(&config.axiom))
+        .flatten()
+        .any(|symbol| matches!(symbol, TurtleSymbol::Plus | TurtleSymbol::Minus));
+    if turns && config.angle.unwrap_or(0) == 0 {
+        report(
+            Severity::Warning,
+            Entry::Angle,
+            "`+` and `-` are used but there is no angle".into(),
+        );
+    }
+
+    let mut sequences = vec![(Entry::Axiom, "axiom".to_string(), &config.axiom)];
+    for (symbol, value) in config.sorted_rules() {
+        sequences.push((
+            Entry::Rule(*symbol),
+            format!("rule for `{}`", symbol),
+            value,
+        ));
+    }
+    for (i, rule) in config.conditional_rules.iter().enumerate() {
+        sequences.push((
+            Entry::ConditionalRule(i),
+            format!("rule `{}`", rule.head()),
+            &rule.value,
+        ));
+    }
+    for (symbol, value) in config.sorted_interpretations() {
+        sequences.push((
+            Entry::Interpret(*symbol),
+            format!("interpretation of `{}`", symbol),
+            value,
+        ));
+    }
+    let has_rule = |symbol: &TurtleSymbol| {
+        config.rules.contains_key(symbol)
+            || config.interpretations.contains_key(symbol)
+            || config
+                .conditional_rules
+                .iter()
+                .any(|rule| rule.symbol == *symbol)
+    };
+    let mut meaningless = Vec::new();
+    for (entry, name, symbols) in &sequences {
+        if let Some(message) = check_brackets(symbols, name) {
+            report(Severity::Error, *entry, message);
+        }
+        let mut messages = Vec::new();
+        let digits = check_colors(symbols, name, &mut messages);
+        for message in messages {
+            report(Severity::Warning, *entry, message);
+        }
+        for (i, symbol) in symbols.iter().enumerate() {
+            let custom = match symbol {
+                TurtleSymbol::CustomSymbol(c) => *c != 'C' && !digits.contains(&i),
+                TurtleSymbol::Named(_) => true,
+                _ => false,
+            };
+            if custom
+                && !has_rule(symbol)
+                && symbol.command(&config.draws) == *symbol
+                && !meaningless.contains(symbol)
+            {
+                meaningless.push(*symbol);
+                report(
+                    Severity::Warning,
+                    *entry,
+                    format!("`{}` has no rule and draws nothing", symbol),
+                );
+            }
+        }
+    }
+
+    if config.region.is_none() {
+        let query = sequences
+            .iter()
+            .find(|(_, _, symbols)| symbols.contains(&TurtleSymbol::Query));
+        if let Some((entry, name, _)) = query {
+            report(
+                Severity::Warning,
+                *entry,
+                format!(
+                    "`?` is used on the {} but there is no region to query",
+                    name
+                ),
+            );
+        }
+    }
+
+    let reachable: HashSet<TurtleSymbol> = config.reachable_symbols().into_iter().collect();
+    for (symbol, _) in config.sorted_rules() {
+        if !reachable.contains(symbol) {
+            report(
+                Severity::Warning,
+                Entry::Rule(*symbol),
+                format!("the rule for `{}` is never reached from the axiom", symbol),
+            );
+        }
+    }
+
+    for (i, rule) in config.conditional_rules.iter().enumerate() {
+        if !reachable.contains(&rule.symbol) {
+            report(
+                Severity::Warning,
+                Entry::ConditionalRule(i),
+                format!("the rule `{}` is never reached from the axiom", rule.head()),
+            );
+        }
+    }
+
+    for (symbol, _) in config.sorted_interpretations() {
+        if !reachable.contains(symbol) {
+            report(
+                Severity::Warning,
+                Entry::Interpret(*symbol),
+                format!(
+                    "the interpretation of `{}` is never reached from the axiom",
+                    symbol
+                ),
+            );
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn messages(text: &str) -> Vec<String> {
+        lint(text, Path::new(""))
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn clean() {
+        assert!(lint("angle 6\norder 2\naxiom C1F\nF = F+F--F+F\n", Path::new("")).is_empty());
+    }
+
+    #[test]
+    fn brackets() {
+        assert_eq!(
+            messages("angle 4\naxiom F]\nF = [[F"),
+            vec![
+                "line 2: error: `]` at position 2 of the axiom has no matching `[`",
+                "line 3: error: 2 `[` of the rule for `F` are never closed",
+            ]
+        );
+    }
+
+    #[test]
+    fn rules() {
+        assert_eq!(
+            messages("angle 4\naxiom FX\nX = +F\nX = -F\ng = GG\n"),
+            vec![
+                "line 4: warning: rule for `X` is defined again, replacing the one of line 3",
+                "line 5: warning: the rule for `g` is never reached from the axiom",
+            ]
+        );
+    }
+
+    #[test]
+    fn symbols() {
+        assert_eq!(
+            messages("axiom C9FYC\nangle x"),
+            vec![
+                "line 1: warning: `C` at position 1 of the axiom is followed by `9` which is not a color from 0 to 6",
+                "line 1: warning: `C` ends the axiom, its color is taken from whatever follows it",
+                "line 1: warning: `9` has no rule and draws nothing",
+                "line 1: warning: `Y` has no rule and draws nothing",
+                "line 2: error: invalid value `x` for angle",
+            ]
+        );
+    }
+
+    #[test]
+    fn queries() {
+        assert_eq!(
+            messages("axiom A\nA = F?[A]"),
+            vec![
+                "line 2: warning: `?` is used on the rule for `A` but there is no region to query"
+            ]
+        );
+        assert!(lint("axiom A\nregion circle 0 0 500\nA = F?[A]", Path::new("")).is_empty());
+    }
+
+    #[test]
+    fn interpretations() {
+        assert_eq!(
+            messages("angle 8\naxiom FL\nF = FF\ninterpret L = [+G-GG-G\ninterpret K = G"),
+            vec![
+                "line 4: error: 1 `[` of the interpretation of `L` are never closed",
+                "line 5: warning: the interpretation of `K` is never reached from the axiom",
+            ]
+        );
+    }
+
+    #[test]
+    fn draws() {
+        assert_eq!(
+            messages("axiom {Fl}{leaf}Xw\ndraw X G\ndraw w"),
+            vec![
+                "line 1: warning: `{Fl}` has no rule and draws nothing",
+                "line 1: warning: `{leaf}` has no rule and draws nothing",
+                "line 1: warning: `w` has no rule and draws nothing",
+                "line 3: error: invalid draw `draw w`, expected a symbol and one of F, G, + or -",
+            ]
+        );
+    }
+}
@@ -6,9 +6,14 @@ where
     T: Debug,
 {
     head: Link<T>,
-    tail: *mut Link<T>,
+    /// Last node pushed, nodes inserted after it through `Node::insert` are found
+    /// by `last`. Pointing to a node on the heap keeps it valid when the list moves.
+    tail: *mut Node<T>,
 }
 
+/// The list owns every node, the tail pointer only points inside of it.
+unsafe impl<T> Send for List<T> where T: Debug + Send {}
+
 pub type Link<T> = Option<Box<Node<T>>>;
 
 #[derive(Debug)]
@@ -38,31 +43,34 @@ where
         self.data = data;
     }
 
-    pub fn replace_with_list(&mut self, list: List<T>) {
-        let next = self.next.take();
-        if let Some(node) = list.head {
-            self.next = node.next;
-            self.data = node.data;
-            unsafe { list.tail.as_mut() }
-                .map(|node| node.as_mut())
-                .flatten()
-                .map(|node| node.next = next);
+    /// Puts the nodes of `list` in place of this one. An empty list leaves the node
+    /// as it is, `List::retain` takes nodes out.
+    pub fn replace_with_list(&mut self, mut list: List<T>) {
+        if let Some(head) = list.head.take() {
+            let head = *head;
+            self.data = head.data;
+            list.head = head.next;
+            self.insert(list);
         }
     }
 
-    pub fn insert(&mut self, list: List<T>) {
+    pub fn insert(&mut self, mut list: List<T>) {
         let next = self.next.take();
-        self.next = list.head;
-        unsafe { list.tail.as_mut() }
-            .map(|node| node.as_mut())
-            .flatten()
-            .map(|node| node.next = next);
+        self.next = list.head.take();
+        let mut link = &mut self.next;
+        while let Some(node) = link {
+            link = &mut node.next;
+        }
+        *link = next;
     }
+}
 
-    fn is_eq(left: &Box<Node<T>>, right: &Box<Node<T>>) -> bool {
-        let left: *const Node<T> = left.as_ref();
-        let right: *const Node<T> = right.as_ref();
-        left == right
+impl<T> Default for List<T>
+where
+    T: Debug,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -77,56 +85,98 @@ where
         }
     }
 
-    pub fn push(&mut self, data: T) {
-        let node = Some(Box::new(Node::new(data)));
-        match unsafe { self.tail.as_mut() } {
-            Some(Some(tail)) => {
-                tail.next = node;
-                self.tail = &mut tail.next;
-            }
-            _ => {
-                self.head = node;
-                self.tail = &mut self.head;
+    /// The last node, moving the tail past nodes inserted after it.
+    fn last(&mut self) -> *mut Node<T> {
+        if self.tail.is_null() {
+            if let Some(head) = self.head.as_mut() {
+                self.tail = head.as_mut();
             }
-        };
+        }
+        while let Some(next) = unsafe { self.tail.as_mut() }.and_then(|tail| tail.next.as_mut()) {
+            self.tail = next.as_mut();
+        }
+        self.tail
+    }
+
+    pub fn push(&mut self, data: T) {
+        let mut node = Box::new(Node::new(data));
+        let tail: *mut Node<T> = node.as_mut();
+        match unsafe { self.last().as_mut() } {
+            Some(last) => last.next = Some(node),
+            None => self.head = Some(node),
+        }
+        self.tail = tail;
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        let node = self.head.take()?;
-        self.head = node.next;
+        let mut node = self.head.take()?;
+        // The tail only moves on `last`, it may still point at the head after nodes
+        // were inserted through `iter_mut`.
+        if std::ptr::eq(node.as_mut(), self.tail) {
+            self.tail = null_mut();
+        }
+        self.head = node.next.take();
         Some(node.data)
     }
 
     pub fn pop_tail(&mut self) -> Option<T> {
-        match (&mut self.head, unsafe { self.tail.as_mut() }?) {
-            (Some(head), Some(tail)) if Node::is_eq(head, tail) => {
-                self.head.take().map(|node| node.data)
-            }
-            (head, Some(tail)) => {
-                let mut node = head;
-                let mut prev_node = null_mut();
-                loop {
-                    prev_node = node;
-                    match node {
-                        Some(inner) => {
-                            node = &mut inner.next;
-                            let is_eq = node
-                                .as_ref()
-                                .map(|node| Node::is_eq(node, tail))
-                                .unwrap_or(true);
-                            if is_eq {
-                                break;
-                            };
-                        }
-                        None => break,
-                    }
-                }
-                let data = unsafe { self.tail.as_mut()? }.take().map(|node| node.data);
-                self.tail = prev_node;
-                data
-            }
-            _ => None,
+        let last: *const Node<T> = self.last();
+        let head = self.head.as_mut()?;
+        if std::ptr::eq(head.as_ref(), last) {
+            self.tail = null_mut();
+            return self.head.take().map(|node| node.data);
+        }
+        let mut node = head;
+        while !node
+            .next
+            .as_deref()
+            .is_some_and(|next| std::ptr::eq(next, last))
+        {
+            node = node.next.as_mut()?;
         }
+        self.tail = node.as_mut();
+        node.next.take().map(|node| node.data)
+    }
+
+    /// Moves every node of `other` to the end of the list.
+    pub fn append(&mut self, mut other: List<T>) {
+        let tail = other.last();
+        let head = match other.head.take() {
+            Some(head) => head,
+            None => return,
+        };
+        match unsafe { self.last().as_mut() } {
+            Some(last) => last.next = Some(head),
+            None => self.head = Some(head),
+        }
+        self.tail = tail;
+    }
+
+    /// Splits the list in two, keeping the first `at` nodes and returning the rest.
+    pub fn split_off(&mut self, at: usize) -> List<T> {
+        let mut rest = List::new();
+        if at == 0 {
+            rest.head = self.head.take();
+            rest.tail = self.tail;
+            self.tail = null_mut();
+            return rest;
+        }
+        let mut node = match self.head.as_mut() {
+            Some(node) => node,
+            None => return rest,
+        };
+        for _ in 1..at {
+            node = match node.next.as_mut() {
+                Some(next) => next,
+                None => return rest,
+            };
+        }
+        rest.head = node.next.take();
+        if rest.head.is_some() {
+            // The tail may be behind the cut, `last` finds the one of `rest` again.
+            self.tail = node.as_mut();
+        }
+        rest
     }
 
     pub fn iter<'a>(&'a self) -> ListIter<'a, T> {
@@ -138,6 +188,34 @@ where
             link: self.head.as_mut().map(|node| node.as_mut()),
         }
     }
+
+    /// Unlinks every node whose data `keep` rejects, visiting them in order so
+    /// `keep` can remember what it saw.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let mut link: *mut Link<T> = &mut self.head;
+        self.tail = null_mut();
+        while let Some(node) = unsafe { (*link).as_mut() } {
+            if keep(&node.data) {
+                self.tail = node.as_mut();
+                link = &mut node.next;
+            } else {
+                let next = node.next.take();
+                unsafe { *link = next };
+            }
+        }
+    }
+}
+
+impl<T> Drop for List<T>
+where
+    T: Debug,
+{
+    fn drop(&mut self) {
+        let mut link = self.head.take();
+        while let Some(mut node) = link {
+            link = node.next.take();
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -177,8 +255,10 @@ where
 
     type IntoIter = ListIntoIter<T>;
 
-    fn into_iter(self) -> Self::IntoIter {
-        ListIntoIter { link: self.head }
+    fn into_iter(mut self) -> Self::IntoIter {
+        ListIntoIter {
+            link: self.head.take(),
+        }
     }
 }
 
@@ -255,6 +335,88 @@ mod test {
         assert_eq!(list.iter().count(), 20);
     }
 
+    #[test]
+    pub fn replace_with_list() {
+        let mut list: List<u32> = (0..3).collect();
+        for elem in list.iter_mut() {
+            if elem.data == 0 {
+                elem.replace_with_list(vec![7].into_iter().collect());
+            } else if elem.data == 1 {
+                elem.replace_with_list(vec![8, 9].into_iter().collect());
+            }
+        }
+        assert_eq!(list.into_iter().collect::<Vec<u32>>(), vec![7, 8, 9, 2]);
+    }
+
+    #[test]
+    pub fn retain() {
+        let mut list: List<u32> = (0..10).collect();
+        list.retain(|data| data % 3 == 1);
+        list.push(10);
+        assert_eq!(list.pop_tail(), Some(10));
+        assert_eq!(list.into_iter().collect::<Vec<u32>>(), vec![1, 4, 7]);
+        let mut list: List<u32> = (0..3).collect();
+        list.retain(|_| false);
+        list.push(5);
+        assert_eq!(list.into_iter().collect::<Vec<u32>>(), vec![5]);
+    }
+
+    #[test]
+    pub fn split_off() {
+        let mut list: List<u32> = (0..10).collect();
+        let mut rest = list.split_off(4);
+        let last = rest.split_off(6);
+        assert!(last.iter().next().is_none());
+        rest.push(10);
+        list.append(rest);
+        list.push(11);
+        assert_eq!(list.pop_tail(), Some(11));
+        let mut moved = List::new();
+        moved.append(list.split_off(0));
+        moved.push(12);
+        assert!(list.iter().next().is_none());
+        assert_eq!(
+            moved.into_iter().collect::<Vec<u32>>(),
+            (0..11).chain(Some(12)).collect::<Vec<u32>>()
+        );
+    }
+
+    #[test]
+    pub fn pop_after_insert() {
+        let mut list: List<u32> = vec![0].into_iter().collect();
+        for elem in list.iter_mut() {
+            elem.replace_with_list(vec![1, 2, 3].into_iter().collect());
+        }
+        assert_eq!(list.pop(), Some(1));
+        let mut last = List::new();
+        last.push(4);
+        list.append(last);
+        list.push(5);
+        assert_eq!(list.pop_tail(), Some(5));
+        let mut list: List<u32> = vec![0].into_iter().collect();
+        list.iter_mut()
+            .next()
+            .unwrap()
+            .insert(vec![1].into_iter().collect());
+        assert_eq!(list.pop(), Some(0));
+        assert_eq!(list.pop(), Some(1));
+        list.push(2);
+        assert_eq!(list.into_iter().collect::<Vec<u32>>(), vec![2]);
+    }
+
+    #[test]
+    pub fn split_off_after_insert() {
+        let mut list: List<u32> = vec![0].into_iter().collect();
+        for elem in list.iter_mut() {
+            elem.replace_with_list(vec![1, 2, 3].into_iter().collect());
+        }
+        let mut rest = list.split_off(1);
+        rest.push(4);
+        list.append(rest);
+        list.push(5);
+        assert_eq!(list.into_iter().collect::<Vec<u32>>(), vec![1, 2, 3, 4, 5]);
+    }
+
     #[test]
     pub fn pop_tail() {
         let mut list: List<u32> = (0..10).collect();
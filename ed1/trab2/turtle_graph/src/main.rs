@@ -1,41 +1,396 @@
 pub mod turtle;
-use std::{fs::File, io::prelude::*, str::FromStr};
+use std::{fs::File, io::prelude::*};
 
 pub use turtle::*;
+pub mod batch;
+pub use batch::*;
+pub mod big;
+pub mod bytecode;
+pub use bytecode::*;
+pub mod condition;
+pub mod cut;
+pub use condition::*;
+pub mod dimension;
+pub use dimension::*;
+pub mod evolve;
+pub use evolve::*;
+pub mod export;
+pub mod golden;
+pub use golden::*;
+pub mod growth;
+pub use growth::*;
+pub mod lint;
+pub use lint::*;
 pub mod list;
 pub use list::*;
+pub mod native;
+pub mod output;
+pub use output::*;
+pub mod path;
+pub mod preview;
+pub use preview::*;
+pub mod repl;
+pub use repl::*;
+pub mod parallel;
+pub use parallel::*;
+pub mod random;
+pub mod region;
+pub use random::*;
+pub use region::*;
+pub mod serialize;
+pub mod stats;
+pub mod symbol;
+#[cfg(test)]
+mod temp;
+pub use symbol::*;
+pub mod trace;
 pub mod tree;
+pub mod tropism;
+pub use path::*;
 pub use tree::*;
+pub use tropism::*;
 
-struct TurtleArgs {
-    input_file_path: String,
-    output_file_path: String,
-    syntax_file_path: String,
+enum TurtleArgs {
+    Render {
+        input_file_path: String,
+        output_file_path: String,
+        syntax_file_path: String,
+    },
+    Preview {
+        input_file_path: String,
+        ansi: bool,
+    },
+    Watch {
+        input_file_path: String,
+        output_file_path: String,
+    },
+    Repl,
+    Check {
+        input_file_paths: Vec<String>,
+    },
+    Growth {
+        input_file_path: String,
+        limit: u64,
+    },
+    Dimension {
+        input_file_path: String,
+    },
+    Stats {
+        input_file_path: String,
+        json: bool,
+    },
+    Expand {
+        input_file_path: String,
+        trace: bool,
+        diff: Option<usize>,
+    },
+    Bench {
+        input_file_path: String,
+        threads: usize,
+    },
+    Batch {
+        input_directory: String,
+        output_directory: String,
+        threads: usize,
+    },
+    Evolve {
+        input_file_path: String,
+        output_directory: String,
+        count: usize,
+        seed: u64,
+    },
 }
 
+const USAGE: &str = r#"Usage:
+    turtle_graph {input_file_path} {output_file_path} {syntax_file_path}
+    turtle_graph preview {input_file_path} [--color]
+    turtle_graph watch {input_file_path} {output_file_path}
+    turtle_graph repl
+    turtle_graph check {input_file_path}...
+    turtle_graph growth {input_file_path} [--limit {symbols}]
+    turtle_graph dimension {input_file_path}
+    turtle_graph stats {input_file_path} [--json]
+    turtle_graph expand {input_file_path} [--trace] [--diff {generation}]
+    turtle_graph bench {input_file_path} [--threads {count}]
+    turtle_graph batch {input_directory} {output_directory} [--threads {count}]
+    turtle_graph evolve {input_file_path} {output_directory} [--count {variants}] [--seed {seed}]"#;
+
 fn get_args() -> Option<TurtleArgs> {
     let mut args = std::env::args();
     args.next()?;
-    let input_file_path = args.next()?;
-    let output_file_path = args.next()?;
-    let syntax_file_path = args.next()?;
-    Some(TurtleArgs {
-        input_file_path,
-        output_file_path,
-        syntax_file_path,
-    })
+    match args.next()?.as_str() {
+        "preview" => {
+            let input_file_path = args.next()?;
+            let ansi = args.any(|arg| arg == "--color");
+            Some(TurtleArgs::Preview {
+                input_file_path,
+                ansi,
+            })
+        }
+        "repl" => Some(TurtleArgs::Repl),
+        "check" => {
+            let input_file_paths: Vec<String> = args.collect();
+            if input_file_paths.is_empty() {
+                return None;
+            }
+            Some(TurtleArgs::Check { input_file_paths })
+        }
+        "growth" => {
+            let input_file_path = args.next()?;
+            let limit = match args.next().as_deref() {
+                Some("--limit") => args.next()?.parse().ok()?,
+                Some(_) => return None,
+                None => GROWTH_LIMIT,
+            };
+            Some(TurtleArgs::Growth {
+                input_file_path,
+                limit,
+            })
+        }
+        "dimension" => Some(TurtleArgs::Dimension {
+            input_file_path: args.next()?,
+        }),
+        "stats" => {
+            let input_file_path = args.next()?;
+            let json = args.any(|arg| arg == "--json");
+            Some(TurtleArgs::Stats {
+                input_file_path,
+                json,
+            })
+        }
+        "expand" => {
+            let input_file_path = args.next()?;
+            let mut trace = false;
+            let mut diff = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--trace" => trace = true,
+                    "--diff" => diff = Some(args.next()?.parse().ok()?),
+                    _ => return None,
+                }
+            }
+            Some(TurtleArgs::Expand {
+                input_file_path,
+                trace,
+                diff,
+            })
+        }
+        "bench" => {
+            let input_file_path = args.next()?;
+            let threads = match args.next().as_deref() {
+                Some("--threads") => args.next()?.parse().ok()?,
+                Some(_) => return None,
+                None => threads(),
+            };
+            Some(TurtleArgs::Bench {
+                input_file_path,
+                threads,
+            })
+        }
+        "batch" => {
+            let input_directory = args.next()?;
+            let output_directory = args.next()?;
+            let threads = match args.next().as_deref() {
+                Some("--threads") => args.next()?.parse().ok()?,
+                Some(_) => return None,
+                None => threads(),
+            };
+            Some(TurtleArgs::Batch {
+                input_directory,
+                output_directory,
+                threads,
+            })
+        }
+        "evolve" => {
+            let input_file_path = args.next()?;
+            let output_directory = args.next()?;
+            let mut count = DEFAULT_VARIANTS;
+            let mut seed = DEFAULT_SEED;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--count" => count = args.next()?.parse().ok().filter(|count| *count > 0)?,
+                    "--seed" => seed = args.next()?.parse().ok()?,
+                    _ => return None,
+                }
+            }
+            Some(TurtleArgs::Evolve {
+                input_file_path,
+                output_directory,
+                count,
+                seed,
+            })
+        }
+        "watch" => {
+            let input_file_path = args.next()?;
+            let output_file_path = args.next()?;
+            Some(TurtleArgs::Watch {
+                input_file_path,
+                output_file_path,
+            })
+        }
+        input_file_path => {
+            let input_file_path = input_file_path.to_string();
+            let output_file_path = args.next()?;
+            let syntax_file_path = args.next()?;
+            Some(TurtleArgs::Render {
+                input_file_path,
+                output_file_path,
+                syntax_file_path,
+            })
+        }
+    }
 }
 
-fn main() -> Result<(), std::io::Error> {
-    let args = get_args().expect(r#"This programm has three arguments {input_file_path} {output_file_path} {syntax_file_path} "#);
+fn read_config(input_file_path: &str) -> Result<TurtleGraphConfig, std::io::Error> {
     let mut input = String::new();
-    File::open(args.input_file_path)?.read_to_string(&mut input)?;
-    let config = TurtleGraphConfig::from_str(&input).unwrap();
-    let syntax = config.generate_syntax();
-    let mut syntax_file = File::create(args.syntax_file_path)?;
-    syntax_file.write_all(syntax.string().as_bytes())?;
-    let mut output_file = File::create(&args.output_file_path)?;
-    println!("Generating output file: {}", args.output_file_path);
-    output_file.write_all(syntax.convert().as_bytes())?;
+    File::open(input_file_path)?.read_to_string(&mut input)?;
+    let path = std::path::Path::new(input_file_path);
+    let directory = path.parent().unwrap_or("".as_ref());
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => TurtleGraphConfig::from_json_in(&input, directory),
+        Some("toml") => TurtleGraphConfig::from_toml_in(&input, directory),
+        _ => TurtleGraphConfig::parse_in(&input, directory),
+    }
+    .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Polls the modification time of the grammar, rendering it again on every change
+/// and reporting errors without leaving the loop.
+fn watch(input_file_path: &str, output_file_path: &str) -> ! {
+    let mut last_modified = None;
+    loop {
+        let modified = std::fs::metadata(input_file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified.is_some() && modified != last_modified {
+            last_modified = modified;
+            let result = read_config(input_file_path).and_then(|config| {
+                write_output(&config, &generate_syntax(&config), output_file_path)
+            });
+            if let Err(error) = result {
+                eprintln!("{}: {}", input_file_path, error);
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    }
+}
+
+fn main() -> Result<(), std::io::Error> {
+    match get_args().expect(USAGE) {
+        TurtleArgs::Render {
+            input_file_path,
+            output_file_path,
+            syntax_file_path,
+        } => {
+            let config = read_config(&input_file_path)?;
+            let syntax = generate_syntax(&config);
+            let mut syntax_file = File::create(syntax_file_path)?;
+            match std::env::var("SYNTAX_MODE").as_deref() {
+                Ok("derivation") => syntax_file.write_all(config.derivation().as_bytes())?,
+                _ => syntax_file.write_all(syntax.string().as_bytes())?,
+            }
+            write_output(&config, &syntax, &output_file_path)?;
+        }
+        TurtleArgs::Preview {
+            input_file_path,
+            ansi,
+        } => {
+            let syntax = generate_syntax(&read_config(&input_file_path)?);
+            let (columns, rows) = terminal_size();
+            print!(
+                "{}",
+                syntax.preview(columns, rows.saturating_sub(1).max(1), ansi)
+            );
+        }
+        TurtleArgs::Watch {
+            input_file_path,
+            output_file_path,
+        } => watch(&input_file_path, &output_file_path),
+        TurtleArgs::Repl => {
+            let (columns, rows) = terminal_size();
+            let stdin = std::io::stdin();
+            Repl::new((columns, rows / 2)).run(stdin.lock(), &mut std::io::stdout())?;
+        }
+        TurtleArgs::Check { input_file_paths } => {
+            let mut errors = false;
+            for input_file_path in input_file_paths {
+                let mut input = String::new();
+                File::open(&input_file_path)?.read_to_string(&mut input)?;
+                let directory = std::path::Path::new(&input_file_path).parent();
+                for diagnostic in lint(&input, directory.unwrap_or("".as_ref())) {
+                    errors |= diagnostic.severity == Severity::Error;
+                    println!("{}: {}", input_file_path, diagnostic);
+                }
+            }
+            if errors {
+                std::process::exit(1);
+            }
+        }
+        TurtleArgs::Growth {
+            input_file_path,
+            limit,
+        } => print!("{}", read_config(&input_file_path)?.growth().report(limit)),
+        TurtleArgs::Stats {
+            input_file_path,
+            json,
+        } => {
+            let stats = read_config(&input_file_path)?.generate_syntax().stats();
+            if json {
+                print!("{}", stats.to_json());
+            } else {
+                print!("{}", stats.table());
+            }
+        }
+        TurtleArgs::Expand {
+            input_file_path,
+            trace,
+            diff,
+        } => {
+            let config = read_config(&input_file_path)?;
+            if trace {
+                print!("{}", config.trace());
+            }
+            match diff.map(|generation| (generation, config.diff(generation))) {
+                Some((_, Some(diff))) => print!("{}", diff),
+                Some((generation, None)) => eprintln!(
+                    "{}: there is no generation after {}",
+                    input_file_path, generation
+                ),
+                None if !trace => println!("{}", config.generate_syntax().string()),
+                None => {}
+            }
+        }
+        TurtleArgs::Bench {
+            input_file_path,
+            threads,
+        } => print!("{}", bench(&read_config(&input_file_path)?, threads)),
+        TurtleArgs::Batch {
+            input_directory,
+            output_directory,
+            threads,
+        } => {
+            let output_directory = std::path::Path::new(&output_directory);
+            let results = batch(input_directory.as_ref(), output_directory, threads)?;
+            print!("{}", batch_summary(&results));
+            println!("gallery: {}", output_directory.join("index.html").display());
+            if results.iter().any(|rendered| rendered.result.is_err()) {
+                std::process::exit(1);
+            }
+        }
+        TurtleArgs::Evolve {
+            input_file_path,
+            output_directory,
+            count,
+            seed,
+        } => {
+            let config = read_config(&input_file_path)?;
+            let stdin = std::io::stdin();
+            Evolution::new(config, count, seed, output_directory)
+                .run(stdin.lock(), &mut std::io::stdout())?;
+        }
+        TurtleArgs::Dimension { input_file_path } => {
+            print!("{}", dimension_report(&read_config(&input_file_path)?))
+        }
+    }
     Ok(())
 }
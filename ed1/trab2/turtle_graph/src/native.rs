@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::{Colors, TurtleGraphConfig, TurtleSymbol, TurtleSyntax};
+
+/// Space separated PostScript tokens, broken in lines of at most 72 chars.
+#[derive(Debug, Default)]
+struct Tokens {
+    value: String,
+    line: usize,
+}
+
+impl Tokens {
+    fn push(&mut self, token: &str) {
+        if self.line + token.len() > 72 {
+            self.value.push('\n');
+            self.line = 0;
+        } else if self.line > 0 {
+            self.value.push(' ');
+            self.line += 1;
+        }
+        self.value.push_str(token);
+        self.line += token.len();
+    }
+
+    fn push_symbols(
+        &mut self,
+        symbols: &[TurtleSymbol],
+        level: u8,
+        rules: &HashMap<TurtleSymbol, Vec<TurtleSymbol>>,
+        draws: &HashMap<TurtleSymbol, TurtleSymbol>,
+    ) {
+        let mut iter = symbols.iter();
+        while let Some(symbol) = iter.next() {
+            if level > 0 && rules.contains_key(symbol) {
+                self.push(&procedure_name(symbol, level));
+            } else if let TurtleSymbol::CustomSymbol('C') = symbol {
+                if let Some(TurtleSymbol::CustomSymbol(c)) = iter.next() {
+                    if Colors::from_digit(*c).is_some() {
+                        self.push(&format!("C{}", c));
+                    }
+                }
+            } else if let Some(command) = native_command(&symbol.command(draws)) {
+                self.push(command);
+            }
+        }
+    }
+}
+
+fn native_command(symbol: &TurtleSymbol) -> Option<&'static str> {
+    match symbol {
+        TurtleSymbol::F => Some("F"),
+        TurtleSymbol::G => Some("G"),
+        TurtleSymbol::Plus => Some("+"),
+        TurtleSymbol::Minus => Some("-"),
+        TurtleSymbol::PushStack => Some("S"),
+        TurtleSymbol::PopStack => Some("R"),
+        TurtleSymbol::Query
+        | TurtleSymbol::Cut
+        | TurtleSymbol::CustomSymbol(_)
+        | TurtleSymbol::Named(_) => None,
+    }
+}
+
+/// Names are built from the char codes since symbols like `+` are not valid on every
+/// position of a PostScript name.
+fn procedure_name(symbol: &TurtleSymbol, level: u8) -> String {
+    let codes: Vec<String> = symbol
+        .to_string()
+        .chars()
+        .map(|c| (c as u32).to_string())
+        .collect();
+    format!("P{}_{}", codes.join("_"), level)
+}
+
+/// Same segment length as the coordinates mode and one procedure per color that
+/// strokes what was drawn so far before switching.
+fn native_preamble() -> String {
+    let mut value = String::from("/len 100 def\n");
+    for digit in '0'..='6' {
+        if let Some(color) = Colors::from_digit(digit) {
+            value.push_str(&format!(
+                "/C{} {{ currentpoint stroke moveto {} }} def\n",
+                digit, color
+            ));
+        }
+    }
+    value
+}
+
+impl TurtleSyntax {
+    /// PostScript output with the derived symbols written as calls to the procedures
+    /// of `preamble_content.txt`, so the interpreter walks the turtle itself. The
+    /// procedures know nothing about jitter or tropism, drawings using them fall
+    /// back to the paths of `convert`.
+    pub fn convert_stream(&self) -> String {
+        if self.jitter.is_some() || self.tropism.is_some() {
+            return self.convert();
+        }
+        let symbols: Vec<TurtleSymbol> = self.list.iter().map(|node| node.data).collect();
+        let mut tokens = Tokens::default();
+        tokens.push_symbols(&symbols, 0, &HashMap::new(), &self.draws);
+        self.postscript(&format!("{}{}\n", native_preamble(), tokens.value))
+    }
+}
+
+impl TurtleGraphConfig {
+    /// PostScript output with one procedure per rule and generation, so the
+    /// derivation itself is done by the interpreter and the file size does not
+    /// depend on the order. Procedures can not tell generations apart, cut branches
+    /// nor see where the turtle is, so grammars with conditional rules,
+    /// interpretations, `%` or a region are streamed instead, as are the ones `convert_stream` leaves to `convert`.
+    pub fn convert_procedures(&self) -> String {
+        let syntax = self.axiom_syntax();
+        if !self.conditional_rules.is_empty()
+            || !self.interpretations.is_empty()
+            || self.prunes()
+            || syntax.jitter.is_some()
+            || syntax.tropism.is_some()
+        {
+            return self.generate_syntax().convert_stream();
+        }
+        let mut value = native_preamble();
+        let mut symbols: Vec<&TurtleSymbol> = self.rules.keys().collect();
+        symbols.sort_by_key(|symbol| symbol.to_string());
+        for level in 1..=syntax.order {
+            for symbol in &symbols {
+                let mut tokens = Tokens::default();
+                tokens.push_symbols(&self.rules[*symbol], level - 1, &self.rules, &self.draws);
+                value.push_str(&format!(
+                    "/{} {{\n{}\n}} def\n",
+                    procedure_name(symbol, level),
+                    tokens.value
+                ));
+            }
+        }
+        let mut tokens = Tokens::default();
+        tokens.push_symbols(&self.axiom, syntax.order, &self.rules, &self.draws);
+        value.push_str(&tokens.value);
+        value.push('\n');
+        syntax.postscript(&value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const KOCH: &str = "angle 6\norder 2\naxiom F\nF = C1F+F--F+F\n";
+
+    #[test]
+    fn stream() {
+        let config = TurtleGraphConfig::from_str(KOCH).unwrap();
+        let value = config.generate_syntax().convert_stream();
+        assert!(value.contains("\nC1 C1 F + F - - F + F + C1 F + F - - F + F - -"));
+    }
+
+    #[test]
+    fn procedures() {
+        let config = TurtleGraphConfig::from_str(KOCH).unwrap();
+        let value = config.convert_procedures();
+        assert!(value.contains("/P70_1 {\nC1 F + F - - F + F\n} def\n"));
+        assert!(value.contains("/P70_2 {\nC1 P70_1 + P70_1 - - P70_1 + P70_1\n} def\n"));
+        assert!(value.contains("\nP70_2\nstroke"));
+    }
+}
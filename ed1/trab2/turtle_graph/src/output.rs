@@ -0,0 +1,60 @@
+use std::{fs::File, io::Write, path::Path};
+
+use crate::{TurtleGraphConfig, TurtleSyntax};
+
+/// Expands the grammar, `COLOR_MODE=generation` colors every segment by the
+/// generation that wrote it instead of the colors of the grammar.
+pub fn generate_syntax(config: &TurtleGraphConfig) -> TurtleSyntax {
+    match std::env::var("COLOR_MODE").as_deref() {
+        Ok("generation") => config.generation_syntax(),
+        _ => config.generate_syntax(),
+    }
+}
+
+/// Picks the output format from the extension of the output file.
+pub fn render(config: &TurtleGraphConfig, syntax: &TurtleSyntax, output_file_path: &str) -> String {
+    let extension = Path::new(output_file_path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    match extension {
+        Some("tex") => syntax.convert_tikz(),
+        Some("html") => syntax.convert_html(),
+        Some("svg") => syntax.convert_svg(),
+        Some("lsy") => config.to_string(),
+        Some("json") => config.to_json(),
+        Some("toml") => config.to_toml(),
+        _ => match std::env::var("POSTSCRIPT_MODE").as_deref() {
+            Ok("stream") => syntax.convert_stream(),
+            Ok("procedures") => config.convert_procedures(),
+            _ => syntax.convert(),
+        },
+    }
+}
+
+/// Writes the rendering of `syntax` to `output_file_path`.
+pub fn write_output(
+    config: &TurtleGraphConfig,
+    syntax: &TurtleSyntax,
+    output_file_path: &str,
+) -> Result<(), std::io::Error> {
+    let mut output_file = File::create(output_file_path)?;
+    println!("Generating output file: {}", output_file_path);
+    output_file.write_all(render(config, syntax, output_file_path).as_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn render_by_extension() {
+        let config = TurtleGraphConfig::from_str("order 1\naxiom F\nF = F+F").unwrap();
+        let syntax = config.generate_syntax();
+        assert_eq!(render(&config, &syntax, "koch.lsy"), config.to_string());
+        assert_eq!(render(&config, &syntax, "koch.json"), config.to_json());
+        assert_eq!(render(&config, &syntax, "koch.svg"), syntax.convert_svg());
+        assert_eq!(render(&config, &syntax, "koch.tex"), syntax.convert_tikz());
+    }
+}
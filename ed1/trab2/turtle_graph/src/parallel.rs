@@ -0,0 +1,429 @@
+use std::{collections::HashMap, time::Instant};
+
+use crate::{
+    turtle::{rewrite, Turtle},
+    Colors, Program, TurtleGraphConfig, TurtleSymbol, TurtleSyntax, Walk,
+};
+
+/// Fewest symbols given to a thread, below it spawning costs more than it saves.
+pub const MIN_CHUNK: usize = 1 << 14;
+
+/// Runs of each measure of `bench`, the fastest one is kept.
+const BENCH_RUNS: usize = 3;
+
+/// Threads used to expand and walk, `THREADS` or one per core of the machine.
+pub fn threads() -> usize {
+    std::env::var("THREADS")
+        .ok()
+        .and_then(|threads| threads.parse().ok())
+        .filter(|threads| *threads > 0)
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |cores| cores.get()))
+}
+
+/// Symbols given to each thread, `None` when the work is not worth splitting.
+fn chunk_size(threads: usize, len: impl FnOnce() -> usize) -> Option<usize> {
+    if threads < 2 {
+        return None;
+    }
+    let len = len();
+    if len < 2 * MIN_CHUNK {
+        return None;
+    }
+    Some(len.div_ceil(threads).max(MIN_CHUNK))
+}
+
+/// How a run of symbols moves the turtle, measured from the position and heading
+/// it starts with.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Motion {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) angle: f32,
+    /// Last color picked, the turtle keeps its own without one.
+    pub(crate) color: Option<Colors>,
+    /// Whether something was drawn since the last `]`.
+    pub(crate) drawn: bool,
+    /// Whether a `]` closed a branch, so what was drawn before it is forgotten.
+    pub(crate) closed: bool,
+}
+
+/// What a chunk does to the turtle, with the branches opened and closed inside of
+/// it left out.
+#[derive(Debug, Clone)]
+enum Move {
+    Motion(Motion),
+    /// A `[` closed on a later chunk.
+    Push,
+    /// A `]` closing a branch opened on an earlier chunk, or nothing.
+    Pop,
+}
+
+/// Where each chunk of about `size` symbols starts, after whole commands.
+fn chunk_bounds(
+    symbols: &[TurtleSymbol],
+    size: usize,
+    draws: &HashMap<TurtleSymbol, TurtleSymbol>,
+) -> Vec<usize> {
+    let is_color =
+        |symbol: &&TurtleSymbol| symbol.command(draws) == TurtleSymbol::CustomSymbol('C');
+    let mut bounds = vec![0];
+    let mut start = size;
+    while start < symbols.len() {
+        // A `C` takes the next symbol whatever it is, so a run of them goes in pairs
+        // and an odd one takes the first symbol of the chunk.
+        let colors = symbols[..start].iter().rev().take_while(is_color).count();
+        start += colors % 2;
+        if start < symbols.len() {
+            bounds.push(start);
+        }
+        start += size;
+    }
+    bounds
+}
+
+/// Sums up what `symbols` do to a turtle without jitter nor tropism, taking steps
+/// of `step` and turns of `turn`.
+fn summary(
+    symbols: &[TurtleSymbol],
+    draws: &HashMap<TurtleSymbol, TurtleSymbol>,
+    step: f32,
+    turn: f32,
+) -> Vec<Move> {
+    // Every `Push` and `Pop` follows the motion before it.
+    let mut moves = Vec::new();
+    let mut motion = Motion::default();
+    let mut iter = symbols.iter();
+    while let Some(symbol) = iter.next() {
+        match symbol.command(draws) {
+            command @ (TurtleSymbol::F | TurtleSymbol::G) => {
+                let (sin, cos) = motion.angle.sin_cos();
+                motion.x += cos * step;
+                motion.y += sin * step;
+                motion.drawn |= command == TurtleSymbol::F;
+            }
+            TurtleSymbol::Plus => motion.angle += turn,
+            TurtleSymbol::Minus => motion.angle -= turn,
+            TurtleSymbol::PushStack => {
+                moves.push(Move::Motion(std::mem::take(&mut motion)));
+                moves.push(Move::Push);
+            }
+            TurtleSymbol::PopStack => {
+                if let Some(Move::Push) = moves.last() {
+                    moves.pop();
+                    if let Some(Move::Motion(saved)) = moves.pop() {
+                        motion = Motion {
+                            drawn: false,
+                            closed: true,
+                            ..saved
+                        };
+                    }
+                } else {
+                    moves.push(Move::Motion(std::mem::take(&mut motion)));
+                    moves.push(Move::Pop);
+                }
+            }
+            TurtleSymbol::CustomSymbol('C') => {
+                if let Some(TurtleSymbol::CustomSymbol(digit)) = iter.next() {
+                    if let Some(color) = Colors::from_digit(*digit) {
+                        motion.color = Some(color);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    moves.push(Move::Motion(motion));
+    moves
+}
+
+impl TurtleSyntax {
+    /// `apply_rules` with the list cut in chunks rewritten by separate threads and
+    /// joined back in order. Rules only look at one symbol, so the chunks do not
+    /// need anything from each other.
+    pub fn apply_rules_parallel(
+        &mut self,
+        rules: &HashMap<TurtleSymbol, Vec<TurtleSymbol>>,
+        threads: usize,
+    ) {
+        let size = match chunk_size(threads, || self.list.iter().count()) {
+            Some(size) => size,
+            None => return self.apply_rules(rules),
+        };
+        let mut rest = std::mem::take(&mut self.list);
+        let mut chunks = Vec::new();
+        while rest.iter().next().is_some() {
+            let next = rest.split_off(size);
+            chunks.push(std::mem::replace(&mut rest, next));
+        }
+        std::thread::scope(|scope| {
+            for chunk in chunks.iter_mut() {
+                scope.spawn(move || rewrite(chunk, rules));
+            }
+        });
+        for chunk in chunks {
+            self.list.append(chunk);
+        }
+    }
+
+    /// Where each chunk of about `size` symbols starts and the turtle there. Each
+    /// chunk is summed up on its own thread and the summaries are followed in
+    /// order, the turtle only walks over every symbol when the jitter draws its
+    /// numbers in order or tropism bends the heading by where it points.
+    fn chunk_starts(&self, symbols: &[TurtleSymbol], size: usize) -> Vec<(usize, Turtle<'_>)> {
+        let bounds = chunk_bounds(symbols, size, &self.draws);
+        let mut turtle = Turtle::new(self);
+        let mut starts = Vec::with_capacity(bounds.len());
+        if turtle.varies() {
+            for (i, start) in bounds.iter().enumerate() {
+                starts.push((*start, turtle.clone()));
+                let end = bounds.get(i + 1).map_or(symbols.len(), |end| *end);
+                let mut iter = symbols[*start..end].iter().copied();
+                while let Some(symbol) = iter.next() {
+                    turtle.step(symbol, &mut iter, None);
+                }
+            }
+            return starts;
+        }
+
+        let (step, turn) = turtle.units();
+        let draws = &self.draws;
+        let summaries: Vec<Vec<Move>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = bounds
+                .windows(2)
+                .map(|chunk| {
+                    let chunk = &symbols[chunk[0]..chunk[1]];
+                    scope.spawn(move || summary(chunk, draws, step, turn))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("a summing thread panicked"))
+                .collect()
+        });
+        starts.push((0, turtle.clone()));
+        for (moves, start) in summaries.iter().zip(&bounds[1..]) {
+            for next in moves {
+                match next {
+                    Move::Motion(motion) => turtle.follow(motion),
+                    Move::Push => {
+                        turtle.push();
+                    }
+                    Move::Pop => {
+                        turtle.pop();
+                    }
+                }
+            }
+            starts.push((*start, turtle.clone()));
+        }
+        starts
+    }
+
+    /// `walk` with the symbols cut in chunks compiled and run by separate threads,
+    /// from the state `chunk_starts` finds at the start of each. Summing the chunks
+    /// up adds the moves in another order, so the points may differ from a single
+    /// run by rounding.
+    pub fn walk_parallel(&self, threads: usize) -> Walk {
+        let mut walk = Walk::default();
+        if threads < 2 {
+            self.compile().run(&mut Turtle::new(self), &mut walk);
+            return walk;
+        }
+        let symbols: Vec<TurtleSymbol> = self.list.iter().map(|node| node.data).collect();
+        let size = match chunk_size(threads, || symbols.len()) {
+            Some(size) => size,
+            None => {
+                Program::compile(symbols, &self.draws).run(&mut Turtle::new(self), &mut walk);
+                return walk;
+            }
+        };
+
+        let starts = self.chunk_starts(&symbols, size);
+        let draws = &self.draws;
+        let walks: Vec<Walk> = std::thread::scope(|scope| {
+            let handles: Vec<_> = starts
+                .iter()
+                .enumerate()
+                .map(|(i, (start, turtle))| {
+                    let end = starts.get(i + 1).map_or(symbols.len(), |(end, _)| *end);
+                    let chunk = &symbols[*start..end];
+                    let mut turtle = turtle.clone();
+                    scope.spawn(move || {
+                        let mut walk = Walk::default();
+                        Program::compile(chunk.iter().copied(), draws).run(&mut turtle, &mut walk);
+                        walk
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("a walking thread panicked"))
+                .collect()
+        });
+
+        for part in walks {
+            walk.segments.extend(part.segments);
+            walk.max_depth = walk.max_depth.max(part.max_depth);
+            walk.tips += part.tips;
+            walk.queries.extend(part.queries);
+            walk.end = part.end;
+            walk.heading = part.heading;
+        }
+        walk
+    }
+}
+
+/// Fastest of `BENCH_RUNS` runs of `run`, in milliseconds.
+fn fastest<T>(mut run: impl FnMut() -> T) -> f64 {
+    (0..BENCH_RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed().as_secs_f64() * 1000.0
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Times the expansion and the walk of `config` on 1, 2, 4... up to `max_threads`
+/// threads, with the speedup over a single thread. Finding where the chunks of
+/// the walk start is also timed alone, it is part of the walk.
+pub fn bench(config: &TurtleGraphConfig, max_threads: usize) -> String {
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |threads| Some(threads * 2))
+        .take_while(|threads| *threads < max_threads)
+        .collect();
+    counts.push(max_threads.max(1));
+
+    let syntax = config.generate_with(1);
+    let mut value = format!(
+        "symbols: {}\nsegments: {}\ncores: {}\n",
+        syntax.list.iter().count(),
+        syntax.walk_parallel(1).segments.len(),
+        std::thread::available_parallelism().map_or(1, |cores| cores.get())
+    );
+    value.push_str(&format!(
+        "{:>7}  {:>12}  {:>10}  {:>11}  {:>7}\n",
+        "threads", "expand (ms)", "walk (ms)", "starts (ms)", "speedup"
+    ));
+    let symbols: Vec<TurtleSymbol> = syntax.list.iter().map(|node| node.data).collect();
+    let mut single = None;
+    for threads in counts {
+        let expand = fastest(|| config.generate_with(threads));
+        let walk = fastest(|| syntax.walk_parallel(threads));
+        let starts = chunk_size(threads, || symbols.len())
+            .map_or(0.0, |size| fastest(|| syntax.chunk_starts(&symbols, size)));
+        let single = *single.get_or_insert(expand + walk);
+        value.push_str(&format!(
+            "{:>7}  {:>12.1}  {:>10.1}  {:>11.1}  {:>6.2}x\n",
+            threads,
+            expand,
+            walk,
+            starts,
+            single / (expand + walk)
+        ));
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::Point;
+
+    #[test]
+    fn expansion() {
+        let config =
+            TurtleGraphConfig::from_str("order 9\naxiom X\nX = F[+X]F[-X]+X\nF = FF").unwrap();
+        let single = config.generate_with(1);
+        assert!(single.list.iter().count() > 4 * MIN_CHUNK);
+        let parallel = config.generate_with(4);
+        assert_eq!(parallel.string(), single.string());
+        // Appending the chunks keeps the tail of the list right.
+        let mut parallel = parallel;
+        parallel.list.push(TurtleSymbol::G);
+        assert_eq!(parallel.list.pop_tail(), Some(TurtleSymbol::G));
+    }
+
+    #[test]
+    fn walk() {
+        let config = TurtleGraphConfig::from_str(
+            "angle 14\norder 8\nangle_jitter 20\nlength_jitter 10\ntropism 0 -1 0.1\naxiom X\nX = C1F[+X?]C2F[-X]+X\nF = FF",
+        )
+        .unwrap();
+        let syntax = config.generate_with(1);
+        assert!(syntax.list.iter().count() > 2 * MIN_CHUNK);
+        let single = syntax.walk_parallel(1);
+        let parallel = syntax.walk_parallel(3);
+        assert_eq!(parallel.segments, single.segments);
+        assert_eq!(parallel.queries, single.queries);
+        assert_eq!(parallel.tips, single.tips);
+        assert_eq!(parallel.max_depth, single.max_depth);
+        assert_eq!(parallel.end, single.end);
+        assert_eq!(parallel.heading, single.heading);
+        let symbols: Vec<TurtleSymbol> = syntax.list.iter().map(|node| node.data).collect();
+        let starts = syntax.chunk_starts(&symbols, MIN_CHUNK);
+        assert!(starts.len() > 1 && starts.iter().all(|(start, _)| *start < symbols.len()));
+    }
+
+    /// Whether `a` and `b` are the same point but for rounding.
+    fn close(a: Point, b: Point) -> bool {
+        (a.x - b.x).abs() <= 0.01 * a.x.abs().max(1.0)
+            && (a.y - b.y).abs() <= 0.01 * a.y.abs().max(1.0)
+    }
+
+    #[test]
+    fn walk_summed() {
+        let config = TurtleGraphConfig::from_str(
+            "angle 14\norder 8\naxiom X]\nX = C1F[+X?]C2F[-X]+X\nF = FF",
+        )
+        .unwrap();
+        let syntax = config.generate_with(1);
+        assert!(syntax.list.iter().count() > 2 * MIN_CHUNK);
+        let single = syntax.walk_parallel(1);
+        let parallel = syntax.walk_parallel(3);
+        assert_eq!(parallel.segments.len(), single.segments.len());
+        assert!(parallel
+            .segments
+            .iter()
+            .zip(&single.segments)
+            .all(|(a, b)| { a.color == b.color && close(a.from, b.from) && close(a.to, b.to) }));
+        assert_eq!(parallel.queries.len(), single.queries.len());
+        assert_eq!(parallel.tips, single.tips);
+        assert_eq!(parallel.max_depth, single.max_depth);
+        assert!(close(parallel.end, single.end));
+    }
+
+    #[test]
+    fn summed_chunks() {
+        let syntax = TurtleGraphConfig::from_str("angle 4\naxiom C2F]+F[C1F[+F]G[F-]F")
+            .unwrap()
+            .generate_syntax();
+        let symbols: Vec<TurtleSymbol> = syntax.list.iter().map(|node| node.data).collect();
+        let draws = HashMap::new();
+        // `C2` is never parted, nor a `C` from the symbol it takes.
+        assert_eq!(chunk_bounds(&symbols, 1, &draws)[..3], [0, 2, 3]);
+        let cc: Vec<TurtleSymbol> = "CCCF".chars().map(TurtleSymbol::CustomSymbol).collect();
+        assert_eq!(chunk_bounds(&cc, 1, &draws), [0, 2]);
+        for size in 1..symbols.len() {
+            let starts = syntax.chunk_starts(&symbols, size);
+            let mut turtle = Turtle::new(&syntax);
+            let mut end = 0;
+            for (start, summed) in starts {
+                let mut iter = symbols[end..start].iter().copied();
+                while let Some(symbol) = iter.next() {
+                    turtle.step(symbol, &mut iter, None);
+                }
+                end = start;
+                assert_eq!(format!("{:.3?}", summed), format!("{:.3?}", turtle));
+            }
+        }
+    }
+
+    #[test]
+    fn bench_columns() {
+        let config = TurtleGraphConfig::from_str("order 2\naxiom F\nF = F+F").unwrap();
+        let bench = bench(&config, 2);
+        assert!(bench.contains("threads   expand (ms)   walk (ms)  starts (ms)  speedup\n"));
+        assert_eq!(bench.lines().count(), 6);
+    }
+}
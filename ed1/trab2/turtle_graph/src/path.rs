@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::Colors;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub from: Point,
+    pub to: Point,
+    pub color: Colors,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub color: Colors,
+    pub points: Vec<Point>,
+}
+
+/// Points are compared on the same grid the outputs are written with (two decimals).
+type PointKey = (i64, i64);
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// The point rotated by `degrees` around the origin, as `rotate` does on PostScript.
+    pub fn rotated(&self, degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    fn key(&self) -> PointKey {
+        (
+            (self.x as f64 * 100.0).round() as i64,
+            (self.y as f64 * 100.0).round() as i64,
+        )
+    }
+}
+
+impl Segment {
+    pub fn new(from: Point, to: Point, color: Colors) -> Self {
+        Self { from, to, color }
+    }
+
+    fn key(&self) -> (PointKey, PointKey, Colors) {
+        let (from, to) = (self.from.key(), self.to.key());
+        if from <= to {
+            (from, to, self.color)
+        } else {
+            (to, from, self.color)
+        }
+    }
+}
+
+fn is_collinear(a: Point, b: Point, c: Point) -> bool {
+    let (ux, uy) = (b.x - a.x, b.y - a.y);
+    let (vx, vy) = (c.x - b.x, c.y - b.y);
+    let cross = ux * vy - uy * vx;
+    let dot = ux * vx + uy * vy;
+    let scale = (ux * ux + uy * uy).sqrt() * (vx * vx + vy * vy).sqrt();
+    dot > 0.0 && cross.abs() <= scale * 1e-4
+}
+
+/// Turns the raw turtle segments into as few paths as possible: exact duplicates
+/// and zero length segments are dropped, a segment starting where a path of its
+/// color ends continues that path, even after the turtle jumped back elsewhere,
+/// and collinear runs collapse into a single edge.
+pub fn optimize(segments: &[Segment]) -> Vec<Path> {
+    let mut seen = HashSet::new();
+    let mut paths: Vec<Path> = Vec::new();
+    // Path ending at each point, for each color. Only the last one to end there
+    // is kept, the others stay as they are.
+    let mut ends: HashMap<(PointKey, Colors), usize> = HashMap::new();
+    for segment in segments {
+        let key = segment.key();
+        if key.0 == key.1 || !seen.insert(key) {
+            continue;
+        }
+        let index = match ends.remove(&(segment.from.key(), segment.color)) {
+            Some(index) => {
+                let path = &mut paths[index];
+                let len = path.points.len();
+                if len >= 2 && is_collinear(path.points[len - 2], path.points[len - 1], segment.to)
+                {
+                    path.points[len - 1] = segment.to;
+                } else {
+                    path.points.push(segment.to);
+                }
+                index
+            }
+            None => {
+                paths.push(Path {
+                    color: segment.color,
+                    points: vec![segment.from, segment.to],
+                });
+                paths.len() - 1
+            }
+        };
+        ends.insert((segment.to.key(), segment.color), index);
+    }
+    paths
+}
+
+/// Lower left and upper right corners of the box containing every path.
+pub fn bounds(paths: &[Path]) -> Option<(Point, Point)> {
+    let mut points = paths.iter().flat_map(|path| path.points.iter());
+    let first = *points.next()?;
+    Some(points.fold((first, first), |(min, max), point| {
+        (
+            Point::new(min.x.min(point.x), min.y.min(point.y)),
+            Point::new(max.x.max(point.x), max.y.max(point.y)),
+        )
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn segment(from: (f32, f32), to: (f32, f32), color: Colors) -> Segment {
+        Segment::new(Point::new(from.0, from.1), Point::new(to.0, to.1), color)
+    }
+
+    #[test]
+    fn merges_collinear_runs() {
+        let paths = optimize(&[
+            segment((0.0, 0.0), (1.0, 0.0), Colors::Black),
+            segment((1.0, 0.0), (2.0, 0.0), Colors::Black),
+            segment((2.0, 0.0), (2.0, 1.0), Colors::Black),
+        ]);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(
+            paths[0].points,
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(2.0, 0.0),
+                Point::new(2.0, 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn does_not_merge_reversals() {
+        let paths = optimize(&[
+            segment((0.0, 0.0), (2.0, 0.0), Colors::Black),
+            segment((2.0, 0.0), (1.0, 0.0), Colors::Black),
+        ]);
+        assert_eq!(paths[0].points.len(), 3);
+    }
+
+    #[test]
+    fn removes_duplicates() {
+        let paths = optimize(&[
+            segment((0.0, 0.0), (1.0, 1.0), Colors::Black),
+            segment((1.0, 1.0), (0.0, 0.0), Colors::Black),
+            segment((0.0, 0.0), (1.0, 1.0), Colors::Red),
+        ]);
+        assert_eq!(paths.len(), 2);
+    }
+
+    #[test]
+    fn continues_earlier_paths() {
+        // The turtle draws elsewhere and comes back to where the first path ends.
+        let paths = optimize(&[
+            segment((0.0, 0.0), (1.0, 0.0), Colors::Black),
+            segment((5.0, 5.0), (6.0, 5.0), Colors::Black),
+            segment((1.0, 0.0), (2.0, 0.0), Colors::Black),
+            segment((6.0, 5.0), (7.0, 5.0), Colors::Red),
+        ]);
+        assert_eq!(paths.len(), 3);
+        assert_eq!(
+            paths[0].points,
+            vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0)]
+        );
+        assert_eq!(paths[2].color, Colors::Red);
+    }
+
+    #[test]
+    fn splits_on_color_and_gaps() {
+        let paths = optimize(&[
+            segment((0.0, 0.0), (1.0, 0.0), Colors::Black),
+            segment((1.0, 0.0), (1.0, 1.0), Colors::Red),
+            segment((5.0, 5.0), (6.0, 5.0), Colors::Red),
+        ]);
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[1].color, Colors::Red);
+    }
+}
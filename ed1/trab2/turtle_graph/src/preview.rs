@@ -0,0 +1,183 @@
+use crate::{bounds, Colors, Point, TurtleSyntax};
+
+/// Bit of each dot inside a braille cell, indexed by `[y][x]`.
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// A canvas where every terminal cell is a braille char holding 2x4 dots.
+#[derive(Debug)]
+pub struct BrailleCanvas {
+    columns: usize,
+    rows: usize,
+    cells: Vec<u8>,
+    colors: Vec<Option<Colors>>,
+}
+
+impl Colors {
+    /// ANSI foreground code, black uses the terminal default so it stays visible on
+    /// dark backgrounds.
+    fn ansi(&self) -> u8 {
+        match self {
+            Colors::Red => 31,
+            Colors::Green => 92,
+            Colors::DarkBlue => 34,
+            Colors::Black => 39,
+            Colors::Brown => 33,
+            Colors::DarkGreen => 32,
+            Colors::White => 97,
+        }
+    }
+}
+
+impl BrailleCanvas {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Self {
+            columns,
+            rows,
+            cells: vec![0; columns * rows],
+            colors: vec![None; columns * rows],
+        }
+    }
+
+    /// Size of the canvas in dots.
+    pub fn dots(&self) -> (usize, usize) {
+        (self.columns * 2, self.rows * 4)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, color: Colors) {
+        let (width, height) = self.dots();
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        let cell = y / 4 * self.columns + x / 2;
+        self.cells[cell] |= BRAILLE_DOTS[y % 4][x % 2];
+        self.colors[cell] = Some(color);
+    }
+
+    /// Bresenham line between two dots.
+    pub fn line(&mut self, from: (i64, i64), to: (i64, i64), color: Colors) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let sx = if x < to.0 { 1 } else { -1 };
+        let sy = if y < to.1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            self.set(x, y, color);
+            if (x, y) == to {
+                break;
+            }
+            let double = 2 * error;
+            if double >= dy {
+                error += dy;
+                x += sx;
+            }
+            if double <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn render(&self, ansi: bool) -> String {
+        let mut value = String::new();
+        for row in 0..self.rows {
+            let mut current = None;
+            for column in 0..self.columns {
+                let cell = row * self.columns + column;
+                let color = self.colors[cell];
+                if ansi && color.is_some() && color != current {
+                    value.push_str(&format!("\x1b[{}m", color.map(|c| c.ansi()).unwrap_or(39)));
+                    current = color;
+                }
+                value.push(std::char::from_u32(0x2800 + self.cells[cell] as u32).unwrap_or(' '));
+            }
+            if ansi && current.is_some() {
+                value.push_str("\x1b[0m");
+            }
+            value.push('\n');
+        }
+        value
+    }
+}
+
+impl TurtleSyntax {
+    /// Rasterises the drawing into a braille canvas of `columns` x `rows` cells,
+    /// keeping its aspect ratio. The canvas takes at least one cell, terminals may
+    /// report a size of 0.
+    pub fn preview(&self, columns: usize, rows: usize, ansi: bool) -> String {
+        let mut canvas = BrailleCanvas::new(columns.max(1), rows.max(1));
+        let paths = self.paths();
+        if let Some((min, max)) = bounds(&paths) {
+            let (width, height) = canvas.dots();
+            let scale = ((width - 1) as f32 / (max.x - min.x).max(f32::EPSILON))
+                .min((height - 1) as f32 / (max.y - min.y).max(f32::EPSILON));
+            let dot = |point: &Point| {
+                (
+                    ((point.x - min.x) * scale).round() as i64,
+                    ((max.y - point.y) * scale).round() as i64,
+                )
+            };
+            for path in &paths {
+                for pair in path.points.windows(2) {
+                    canvas.line(dot(&pair[0]), dot(&pair[1]), path.color);
+                }
+            }
+        }
+        canvas.render(ansi)
+    }
+}
+
+/// Size of the terminal in cells from `COLUMNS`/`LINES` or `stty`, defaulting to 80x24.
+pub fn terminal_size() -> (usize, usize) {
+    let from_env = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok());
+    if let (Some(columns), Some(rows)) = (from_env("COLUMNS"), from_env("LINES")) {
+        return (columns, rows);
+    }
+    std::fs::File::open("/dev/tty")
+        .ok()
+        .and_then(|tty| {
+            std::process::Command::new("stty")
+                .arg("size")
+                .stdin(tty)
+                .output()
+                .ok()
+        })
+        .and_then(|output| {
+            let size = String::from_utf8(output.stdout).ok()?;
+            let mut size = size.split_whitespace().map(str::parse);
+            match (size.next(), size.next()) {
+                (Some(Ok(rows)), Some(Ok(columns))) => Some((columns, rows)),
+                _ => None,
+            }
+        })
+        .unwrap_or((80, 24))
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::TurtleGraphConfig;
+
+    #[test]
+    fn line() {
+        let mut canvas = BrailleCanvas::new(2, 1);
+        canvas.line((0, 0), (3, 3), Colors::Black);
+        assert_eq!(canvas.render(false), "\u{2811}\u{2884}\n");
+    }
+
+    #[test]
+    fn preview() {
+        let syntax = TurtleGraphConfig::from_str("angle 4\norder 0\naxiom F+F")
+            .unwrap()
+            .generate_syntax();
+        assert_eq!(syntax.preview(2, 1, false), "\u{28c0}\u{28f8}\n");
+        assert_eq!(
+            syntax.preview(2, 1, true),
+            "\x1b[39m\u{28c0}\u{28f8}\x1b[0m\n"
+        );
+        assert_eq!(syntax.preview(0, 0, false), "\u{281a}\n");
+    }
+}
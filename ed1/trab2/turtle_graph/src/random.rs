@@ -0,0 +1,58 @@
+/// Seed used when a grammar asks for randomness without giving one.
+pub const DEFAULT_SEED: u64 = 1;
+
+/// Small splitmix64 generator, the same seed always gives the same drawing.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+
+    /// Uniform value from 0 to 1, 1 excluded.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform value from -1 to 1.
+    pub fn signed(&mut self) -> f32 {
+        self.next_f32() * 2.0 - 1.0
+    }
+
+    /// Uniform index from 0 to `n`, `n` excluded.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let values: Vec<u64> = {
+            let mut random = Random::new(7);
+            (0..4).map(|_| random.next_u64()).collect()
+        };
+        let mut random = Random::new(7);
+        assert_eq!(
+            values,
+            (0..4).map(|_| random.next_u64()).collect::<Vec<_>>()
+        );
+        assert_ne!(Random::new(8).next_u64(), values[0]);
+        assert!((0..1000).all(|_| (-1.0..1.0).contains(&random.signed())));
+        assert!((0..1000).all(|_| random.below(3) < 3));
+    }
+}
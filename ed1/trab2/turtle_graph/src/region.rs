@@ -0,0 +1,240 @@
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use crate::{Point, TurtleGraphConfig, TurtleSymbol, TurtleSyntax};
+
+/// Space a grammar is allowed to grow into, written `region circle {x} {y} {radius}`,
+/// `region box {x0} {y0} {x1} {y1}` or `region polygon {file}`. Coordinates are on
+/// the page, with `rotate` applied and 100 units for each step. The polygon file is
+/// found next to the grammar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Region {
+    Circle {
+        center: Point,
+        radius: f32,
+    },
+    Box {
+        min: Point,
+        max: Point,
+    },
+    /// Corners read from `path`, one `x y` pair per line, `;` starting comments.
+    /// `path` is kept as written, relative to the directory of the grammar.
+    Polygon {
+        path: String,
+        points: Vec<Point>,
+    },
+}
+
+fn parse_numbers(s: &str) -> Option<Vec<f32>> {
+    s.split_whitespace().map(|word| word.parse().ok()).collect()
+}
+
+fn read_polygon(path: &str, directory: &Path) -> Result<Vec<Point>, String> {
+    let text = std::fs::read_to_string(directory.join(path))
+        .map_err(|error| format!("can not read the region polygon `{}`: {}", path, error))?;
+    let mut points = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line[..line.find(';').unwrap_or(line.len())].trim();
+        if line.is_empty() {
+            continue;
+        }
+        match parse_numbers(line).as_deref() {
+            Some([x, y]) => points.push(Point::new(*x, *y)),
+            _ => {
+                return Err(format!(
+                    "line {} of the region polygon `{}` is not a `x y` pair",
+                    i + 1,
+                    path
+                ))
+            }
+        }
+    }
+    if points.len() < 3 {
+        return Err(format!(
+            "the region polygon `{}` needs at least 3 points",
+            path
+        ));
+    }
+    Ok(points)
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Region::parse_in(s, Path::new(""))
+    }
+}
+
+impl Region {
+    /// Parses a region of a grammar on `directory`, a polygon file is read from there.
+    pub fn parse_in(s: &str, directory: &Path) -> Result<Self, String> {
+        let s = s.trim();
+        let (shape, rest) = s.split_at(s.find(char::is_whitespace).unwrap_or(s.len()));
+        let rest = rest.trim();
+        let region = match (shape, parse_numbers(rest).as_deref()) {
+            ("circle", Some([x, y, radius])) => Region::Circle {
+                center: Point::new(*x, *y),
+                radius: *radius,
+            },
+            ("box", Some([x0, y0, x1, y1])) => Region::Box {
+                min: Point::new(x0.min(*x1), y0.min(*y1)),
+                max: Point::new(x0.max(*x1), y0.max(*y1)),
+            },
+            ("polygon", _) if !rest.is_empty() => Region::Polygon {
+                path: rest.into(),
+                points: read_polygon(rest, directory)?,
+            },
+            _ => {
+                return Err(format!(
+                    "invalid region `{}`, expected `circle x y radius`, `box x0 y0 x1 y1` or `polygon file`",
+                    s
+                ))
+            }
+        };
+        Ok(region)
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Region::Circle { center, radius } => {
+                write!(f, "circle {} {} {}", center.x, center.y, radius)
+            }
+            Region::Box { min, max } => write!(f, "box {} {} {} {}", min.x, min.y, max.x, max.y),
+            Region::Polygon { path, .. } => write!(f, "polygon {}", path),
+        }
+    }
+}
+
+impl Region {
+    /// Whether `point` is inside the region or on its border.
+    pub fn contains(&self, point: &Point) -> bool {
+        match self {
+            Region::Circle { center, radius } => {
+                let (dx, dy) = (point.x - center.x, point.y - center.y);
+                dx * dx + dy * dy <= radius * radius
+            }
+            Region::Box { min, max } => {
+                (min.x..=max.x).contains(&point.x) && (min.y..=max.y).contains(&point.y)
+            }
+            // Counts the sides crossed by a ray going right from the point.
+            Region::Polygon { points, .. } => {
+                let mut inside = false;
+                for (i, a) in points.iter().enumerate() {
+                    let b = &points[(i + 1) % points.len()];
+                    if (a.y > point.y) != (b.y > point.y) {
+                        let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                        if point.x < x {
+                            inside = !inside;
+                        }
+                    }
+                }
+                inside
+            }
+        }
+    }
+}
+
+impl TurtleSyntax {
+    /// For each `?` of `symbols`, whether the turtle reaches it outside `region`.
+    pub(crate) fn outside(
+        &self,
+        region: &Region,
+        symbols: impl Iterator<Item = TurtleSymbol>,
+    ) -> Vec<bool> {
+        self.walk_symbols(symbols)
+            .queries
+            .iter()
+            .map(|point| !region.contains(&point.rotated(self.rotate)))
+            .collect()
+    }
+
+    /// Turns every `?` the turtle reaches outside `region` into `%`.
+    pub fn query(&mut self, region: &Region) {
+        let mut outside = self
+            .outside(region, self.list.iter().map(|node| node.data))
+            .into_iter();
+        for node in self.list.iter_mut() {
+            if node.data == TurtleSymbol::Query && outside.next() == Some(true) {
+                node.replace(TurtleSymbol::Cut);
+            }
+        }
+    }
+}
+
+impl TurtleGraphConfig {
+    /// Queries the region on symbols kept with their ages, as `query` does on a
+    /// syntax.
+    pub(crate) fn query_aged(&self, symbols: &mut [(TurtleSymbol, usize)]) {
+        let region = match &self.region {
+            Some(region) => region,
+            None => return,
+        };
+        let outside = self
+            .axiom_syntax()
+            .outside(region, symbols.iter().map(|(symbol, _)| *symbol));
+        let mut outside = outside.into_iter();
+        for (symbol, _) in symbols.iter_mut() {
+            if *symbol == TurtleSymbol::Query && outside.next() == Some(true) {
+                *symbol = TurtleSymbol::Cut;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp::TempDir;
+
+    #[test]
+    fn regions() {
+        let circle: Region = "circle 0 100 50".parse().unwrap();
+        assert_eq!(circle.to_string(), "circle 0 100 50");
+        assert!(circle.contains(&Point::new(0.0, 50.0)));
+        assert!(!circle.contains(&Point::new(40.0, 60.0)));
+        let area: Region = "box 10 10 -10 -10".parse().unwrap();
+        assert_eq!(area.to_string(), "box -10 -10 10 10");
+        assert!(area.contains(&Point::new(10.0, 0.0)) && !area.contains(&Point::new(0.0, 11.0)));
+        let triangle = Region::Polygon {
+            path: "triangle".into(),
+            points: vec![
+                Point::new(0.0, 0.0),
+                Point::new(10.0, 0.0),
+                Point::new(0.0, 10.0),
+            ],
+        };
+        assert!(triangle.contains(&Point::new(2.0, 2.0)));
+        assert!(!triangle.contains(&Point::new(6.0, 6.0)));
+        assert!("circle 0 0".parse::<Region>().is_err());
+        assert!("polygon".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn polygon_next_to_grammar() {
+        let temp = TempDir::new("region");
+        let directory = temp.path();
+        std::fs::write(directory.join("triangle.txt"), "0 0\n10 0 ; corner\n0 10\n").unwrap();
+        let config = TurtleGraphConfig::parse_in("region polygon triangle.txt\naxiom F", directory);
+        let region = config.unwrap().region.unwrap();
+        assert_eq!(region.to_string(), "polygon triangle.txt");
+        assert!(region.contains(&Point::new(2.0, 2.0)));
+        assert!("polygon triangle.txt".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn queries() {
+        // The third branches end past the box, so their `?` cuts what would grow on them.
+        let text =
+            "angle 8\norder 4\nrotate 90\nregion box -150 0 150 250\naxiom A\nA = F?[+A][-A]";
+        let config: TurtleGraphConfig = text.parse().unwrap();
+        let syntax = config.generate_syntax();
+        assert_eq!(syntax.string(), "F?[+F?[+F][-F]][-F?[+F][-F]]");
+        let conditional: TurtleGraphConfig = format!("{}\nG : t > 9 = G", text).parse().unwrap();
+        assert_eq!(conditional.generate_syntax().string(), syntax.string());
+        let tree = config.derivation_tree();
+        let leaves: Vec<TurtleSymbol> = tree.leaves().iter().map(|&i| tree.get(i).data).collect();
+        assert_eq!(crate::symbols_to_string(&leaves), syntax.string());
+    }
+}
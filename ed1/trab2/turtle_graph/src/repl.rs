@@ -0,0 +1,143 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{optimize, write_output, TurtleGraphConfig};
+
+/// Longest expansion printed after each command.
+const SHOWN_SYMBOLS: usize = 200;
+
+const HELP: &str = r#"Any grammar line changes the current grammar:
+    angle 6 | order 3 | rotate 45 | axiom F | F = F+F--F+F
+Commands:
+    show            prints the grammar, its expansion and a preview
+    stats           prints the size of the expansion and of the drawing
+    save {file}     renders to a file, the format follows its extension
+    export {file}   writes the grammar lines of the session to a .lsy file
+    reset           starts an empty grammar
+    help            prints this message
+    quit            leaves the repl"#;
+
+/// An interactive session building a grammar one line at a time.
+#[derive(Debug)]
+pub struct Repl {
+    config: TurtleGraphConfig,
+    history: Vec<String>,
+    preview: (usize, usize),
+}
+
+impl Repl {
+    /// `preview` is the size in cells of the preview printed after each change.
+    pub fn new(preview: (usize, usize)) -> Self {
+        Self {
+            config: TurtleGraphConfig::default(),
+            history: Vec::new(),
+            preview,
+        }
+    }
+
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            if !self.command(&line?, output)? {
+                break;
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Runs a single command, returning `false` when the session is over.
+    pub fn command(&mut self, line: &str, output: &mut impl Write) -> io::Result<bool> {
+        let line = line.trim();
+        let (command, argument) = match line.find(' ') {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        match (command, argument) {
+            ("", _) => {}
+            ("quit", "") | ("exit", "") => return Ok(false),
+            ("help", "") => writeln!(output, "{}", HELP)?,
+            ("show", "") => {
+                writeln!(output, "{}", self.session())?;
+                self.show(output)?;
+            }
+            ("stats", "") => self.stats(output)?,
+            ("reset", "") => {
+                self.config = TurtleGraphConfig::default();
+                self.history.clear();
+            }
+            ("save", path) if !path.is_empty() => {
+                let syntax = self.config.generate_syntax();
+                if let Err(error) = write_output(&self.config, &syntax, path) {
+                    writeln!(output, "{}: {}", path, error)?;
+                }
+            }
+            ("export", path) if !path.is_empty() => {
+                if let Err(error) = std::fs::write(path, self.session()) {
+                    writeln!(output, "{}: {}", path, error)?;
+                }
+            }
+            _ => match self.config.parse_line(line, self.history.len() + 1) {
+                Ok(_) => {
+                    self.history.push(line.to_string());
+                    self.show(output)?;
+                }
+                Err(error) => writeln!(output, "{}", error.message)?,
+            },
+        }
+        Ok(true)
+    }
+
+    /// The grammar lines accepted so far, as a `.lsy` file.
+    pub fn session(&self) -> String {
+        let mut value = self.history.join("\n");
+        value.push('\n');
+        value
+    }
+
+    fn show(&self, output: &mut impl Write) -> io::Result<()> {
+        let syntax = self.config.generate_syntax();
+        let string = syntax.string();
+        let count = string.chars().count();
+        if count > SHOWN_SYMBOLS {
+            let shown: String = string.chars().take(SHOWN_SYMBOLS).collect();
+            writeln!(output, "{}... ({} symbols)", shown, count)?;
+        } else {
+            writeln!(output, "{}", string)?;
+        }
+        if syntax.segments().is_empty() {
+            return Ok(());
+        }
+        write!(
+            output,
+            "{}",
+            syntax.preview(self.preview.0, self.preview.1, true)
+        )
+    }
+
+    fn stats(&self, output: &mut impl Write) -> io::Result<()> {
+        let syntax = self.config.generate_syntax();
+        writeln!(output, "symbols: {}", syntax.string().chars().count())?;
+        writeln!(output, "paths: {}", optimize(&syntax.segments()).len())?;
+        write!(output, "{}", syntax.stats().table())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn session() {
+        let mut repl = Repl::new((4, 2));
+        let mut output = Vec::new();
+        let input = "angle 6\naxiom F\nF = F+F--F+F\norder two\norder 1\nstats\nquit\nshow\n";
+        repl.run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("F+F--F+F\n"));
+        assert!(output.contains("invalid value `two` for order\n"));
+        assert!(output.contains("segments: 4\n"));
+        assert_eq!(repl.session(), "angle 6\naxiom F\nF = F+F--F+F\norder 1\n");
+    }
+}
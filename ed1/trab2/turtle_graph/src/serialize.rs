@@ -0,0 +1,746 @@
+use std::{fmt::Write, iter::Peekable, path::Path, str::Chars};
+
+use crate::{
+    parse_draw, parse_symbol, parse_symbols, symbols_to_string, ConditionalRule, ParseError,
+    Region, TurtleGraphConfig, TurtleSymbol,
+};
+
+/// The values a grammar is made of, which is all the JSON and TOML readers support.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Null,
+    Integer(i64),
+    String(String),
+    /// Fields with the line their value starts on.
+    Table(Fields),
+}
+
+type Fields = Vec<(String, usize, Value)>;
+
+/// Quoted string with the escapes shared by JSON and TOML basic strings.
+pub(crate) fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Cursor<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str, line: usize) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+            line,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.next();
+        }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(format!("expected `{}`, found `{}`", expected, c))),
+            None => Err(self.error(format!("expected `{}`, found the end", expected))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let code: String = (0..4).filter_map(|_| self.next()).collect();
+                            u32::from_str_radix(&code, 16)
+                                .ok()
+                                .and_then(std::char::from_u32)
+                                .ok_or_else(|| {
+                                    self.error(format!("invalid escape `\\u{}`", code))
+                                })?
+                        }
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        c => {
+                            return Err(
+                                self.error(format!("invalid escape `\\{}`", c.unwrap_or(' ')))
+                            )
+                        }
+                    };
+                    value.push(c);
+                }
+                Some('\n') | None => return Err(self.error("unterminated string".into())),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, ParseError> {
+        let mut digits = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '+' || c == '.' {
+                digits.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+        digits
+            .parse()
+            .map_err(|_| self.error(format!("expected an integer, found `{}`", digits)))
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<(), ParseError> {
+        literal.chars().try_for_each(|c| self.expect(c))
+    }
+
+    fn json(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.next();
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.next();
+                    return Ok(Value::Table(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(':')?;
+                    self.skip_whitespace();
+                    let line = self.line;
+                    fields.push((key, line, self.json()?));
+                    self.skip_whitespace();
+                    match self.next() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Value::Table(fields)),
+                        _ => return Err(self.error("expected `,` or `}`".into())),
+                    }
+                }
+            }
+            Some('"') => self.string().map(Value::String),
+            Some('n') => self.literal("null").map(|_| Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.integer().map(Value::Integer),
+            Some(c) => Err(self.error(format!("unexpected `{}`", c))),
+            None => Err(self.error("unexpected end".into())),
+        }
+    }
+
+    /// Reads `key = value` of a TOML line, the key is bare or quoted and the value an
+    /// integer or a string.
+    fn toml_field(&mut self) -> Result<(String, Value), ParseError> {
+        let key = if self.peek() == Some('"') {
+            self.string()?
+        } else {
+            let mut key = String::new();
+            while let Some(c) = self.peek() {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    key.push(c);
+                    self.next();
+                } else {
+                    break;
+                }
+            }
+            key
+        };
+        self.skip_whitespace();
+        self.expect('=')?;
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some('"') => Value::String(self.string()?),
+            _ => Value::Integer(self.integer()?),
+        };
+        self.skip_whitespace();
+        match self.peek() {
+            None | Some('#') => Ok((key, value)),
+            Some(c) => Err(self.error(format!("unexpected `{}`", c))),
+        }
+    }
+}
+
+fn integer<T: std::convert::TryFrom<i64>>(
+    value: Value,
+    name: &str,
+    line: usize,
+) -> Result<Option<T>, ParseError> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Integer(i) => T::try_from(i).map(Some).map_err(|_| ParseError {
+            line,
+            message: format!("{} out of range for {}", i, name),
+        }),
+        _ => Err(ParseError {
+            line,
+            message: format!("expected an integer for {}", name),
+        }),
+    }
+}
+
+fn symbols(value: Value, name: &str, line: usize) -> Result<Vec<TurtleSymbol>, ParseError> {
+    match value {
+        Value::String(s) => parse_symbols(&s).map_err(|message| ParseError { line, message }),
+        _ => Err(ParseError {
+            line,
+            message: format!("expected a string for {}", name),
+        }),
+    }
+}
+
+fn config_from_fields(
+    fields: Vec<(String, usize, Value)>,
+    directory: &Path,
+) -> Result<TurtleGraphConfig, ParseError> {
+    let mut config = TurtleGraphConfig {
+        directory: directory.into(),
+        ..TurtleGraphConfig::default()
+    };
+    for (key, line, value) in fields {
+        match key.as_str() {
+            "angle" => config.angle = integer(value, "angle", line)?,
+            "order" => config.order = integer(value, "order", line)?,
+            "rotate" => config.rotate = integer(value, "rotate", line)?,
+            "angle_jitter" => config.angle_jitter = integer(value, "angle_jitter", line)?,
+            "length_jitter" => config.length_jitter = integer(value, "length_jitter", line)?,
+            // Seeds are written as strings, integers only go up to `i64::MAX`.
+            "seed" => {
+                config.seed = match value {
+                    Value::String(seed) => Some(seed.parse().map_err(|_| ParseError {
+                        line,
+                        message: format!("invalid value `{}` for seed", seed),
+                    })?),
+                    value => integer(value, "seed", line)?,
+                }
+            }
+            "tropism" => {
+                config.tropism = match value {
+                    Value::String(value) => Some(value.parse().map_err(|_| ParseError {
+                        line,
+                        message: format!("invalid value `{}` for tropism", value),
+                    })?),
+                    _ => {
+                        return Err(ParseError {
+                            line,
+                            message: "expected a string like \"0 -1 0.2\" for tropism".into(),
+                        })
+                    }
+                }
+            }
+            "region" => {
+                config.region = match value {
+                    Value::String(value) => Some(
+                        Region::parse_in(&value, &config.directory)
+                            .map_err(|message| ParseError { line, message })?,
+                    ),
+                    _ => {
+                        return Err(ParseError {
+                            line,
+                            message: "expected a string like \"circle 0 300 250\" for region"
+                                .into(),
+                        })
+                    }
+                }
+            }
+            "draw" => match value {
+                Value::Table(draws) => {
+                    for (symbol, line, value) in draws {
+                        let command = match value {
+                            Value::String(command) => command,
+                            _ => String::new(),
+                        };
+                        let (symbol, command) = parse_draw(&format!("{} {}", symbol, command))
+                            .ok_or_else(|| ParseError {
+                                line,
+                                message: format!(
+                                    "invalid draw for `{}`, expected one of \"F\", \"G\", \"+\" or \"-\"",
+                                    symbol
+                                ),
+                            })?;
+                        config.draws.insert(symbol, command);
+                    }
+                }
+                _ => {
+                    return Err(ParseError {
+                        line,
+                        message: "expected a table of draws".into(),
+                    })
+                }
+            },
+            "interpret" => match value {
+                Value::Table(interpretations) => {
+                    for (symbol, line, value) in interpretations {
+                        match parse_symbol(&symbol) {
+                            Ok(Some((head, ""))) => {
+                                let value = symbols(value, &symbol, line)?;
+                                config.interpretations.insert(head, value);
+                            }
+                            _ => {
+                                return Err(ParseError {
+                                    line,
+                                    message: format!(
+                                        "interpretation `{}` is not a single symbol",
+                                        symbol
+                                    ),
+                                })
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    return Err(ParseError {
+                        line,
+                        message: "expected a table of interpretations".into(),
+                    })
+                }
+            },
+            "axiom" => config.axiom = symbols(value, "axiom", line)?,
+            "rules" => match value {
+                Value::Table(rules) => {
+                    for (symbol, line, value) in rules {
+                        let head = symbol.replace(" ", "");
+                        match parse_symbol(&head) {
+                            Ok(Some((c, ""))) => {
+                                let value = symbols(value, &symbol, line)?;
+                                config.rules.insert(c, value);
+                            }
+                            _ => {
+                                if let Some((symbol, condition, "")) =
+                                    ConditionalRule::parse_head(&head)
+                                {
+                                    config.conditional_rules.push(ConditionalRule {
+                                        symbol,
+                                        condition,
+                                        value: symbols(value, &head, line)?,
+                                    });
+                                } else {
+                                    return Err(ParseError {
+                                    line,
+                                    message: format!(
+                                        "rule `{}` is not a single symbol or a symbol with a condition",
+                                        symbol
+                                    ),
+                                });
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    return Err(ParseError {
+                        line,
+                        message: "expected a table of rules".into(),
+                    })
+                }
+            },
+            key => {
+                return Err(ParseError {
+                    line,
+                    message: format!("unknown field `{}`", key),
+                })
+            }
+        }
+    }
+    Ok(config)
+}
+
+impl TurtleGraphConfig {
+    /// Keys and values of the rules table, the plain rules sorted by symbol then the
+    /// conditional ones in order.
+    fn rule_entries(&self) -> Vec<(String, &Vec<TurtleSymbol>)> {
+        let mut entries: Vec<_> = self
+            .sorted_rules()
+            .into_iter()
+            .map(|(symbol, value)| (symbol.to_string(), value))
+            .collect();
+        entries.extend(
+            self.conditional_rules
+                .iter()
+                .map(|rule| (rule.head(), &rule.value)),
+        );
+        entries
+    }
+
+    /// The grammar as a JSON object, comments are only kept by the `.lsy` format.
+    pub fn to_json(&self) -> String {
+        let mut fields = Vec::new();
+        if let Some(angle) = self.angle {
+            fields.push(format!("  \"angle\": {}", angle));
+        }
+        if let Some(order) = self.order {
+            fields.push(format!("  \"order\": {}", order));
+        }
+        if let Some(rotate) = self.rotate {
+            fields.push(format!("  \"rotate\": {}", rotate));
+        }
+        if let Some(angle_jitter) = self.angle_jitter {
+            fields.push(format!("  \"angle_jitter\": {}", angle_jitter));
+        }
+        if let Some(length_jitter) = self.length_jitter {
+            fields.push(format!("  \"length_jitter\": {}", length_jitter));
+        }
+        if let Some(seed) = self.seed {
+            fields.push(format!("  \"seed\": {}", quote(&seed.to_string())));
+        }
+        if let Some(tropism) = self.tropism {
+            fields.push(format!("  \"tropism\": {}", quote(&tropism.to_string())));
+        }
+        if let Some(region) = &self.region {
+            fields.push(format!("  \"region\": {}", quote(&region.to_string())));
+        }
+        let draws: Vec<String> = self
+            .sorted_draws()
+            .into_iter()
+            .map(|(symbol, command)| {
+                format!(
+                    "    {}: {}",
+                    quote(&symbol.to_string()),
+                    quote(&command.to_string())
+                )
+            })
+            .collect();
+        if !draws.is_empty() {
+            fields.push(format!("  \"draw\": {{\n{}\n  }}", draws.join(",\n")));
+        }
+        fields.push(format!(
+            "  \"axiom\": {}",
+            quote(&symbols_to_string(&self.axiom))
+        ));
+        let rules: Vec<String> = self
+            .rule_entries()
+            .into_iter()
+            .map(|(head, value)| {
+                format!("    {}: {}", quote(&head), quote(&symbols_to_string(value)))
+            })
+            .collect();
+        if rules.is_empty() {
+            fields.push("  \"rules\": {}".into());
+        } else {
+            fields.push(format!("  \"rules\": {{\n{}\n  }}", rules.join(",\n")));
+        }
+        let interpretations: Vec<String> = self
+            .sorted_interpretations()
+            .into_iter()
+            .map(|(symbol, value)| {
+                format!(
+                    "    {}: {}",
+                    quote(&symbol.to_string()),
+                    quote(&symbols_to_string(value))
+                )
+            })
+            .collect();
+        if !interpretations.is_empty() {
+            fields.push(format!(
+                "  \"interpret\": {{\n{}\n  }}",
+                interpretations.join(",\n")
+            ));
+        }
+        format!("{{\n{}\n}}\n", fields.join(",\n"))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ParseError> {
+        TurtleGraphConfig::from_json_in(text, Path::new(""))
+    }
+
+    /// `from_json` for a file on `directory`.
+    pub fn from_json_in(text: &str, directory: &Path) -> Result<Self, ParseError> {
+        let mut cursor = Cursor::new(text, 1);
+        let value = cursor.json()?;
+        cursor.skip_whitespace();
+        if let Some(c) = cursor.peek() {
+            return Err(cursor.error(format!("unexpected `{}` after the object", c)));
+        }
+        match value {
+            Value::Table(fields) => config_from_fields(fields, directory),
+            _ => Err(ParseError {
+                line: 1,
+                message: "expected an object".into(),
+            }),
+        }
+    }
+
+    /// The grammar as a TOML document with the rules on a `[rules]` table, the
+    /// interpretations on an `[interpret]` one and the draws on a `[draw]` one.
+    pub fn to_toml(&self) -> String {
+        let mut value = String::new();
+        if let Some(angle) = self.angle {
+            value.push_str(&format!("angle = {}\n", angle));
+        }
+        if let Some(order) = self.order {
+            value.push_str(&format!("order = {}\n", order));
+        }
+        if let Some(rotate) = self.rotate {
+            value.push_str(&format!("rotate = {}\n", rotate));
+        }
+        if let Some(angle_jitter) = self.angle_jitter {
+            value.push_str(&format!("angle_jitter = {}\n", angle_jitter));
+        }
+        if let Some(length_jitter) = self.length_jitter {
+            value.push_str(&format!("length_jitter = {}\n", length_jitter));
+        }
+        if let Some(seed) = self.seed {
+            value.push_str(&format!("seed = {}\n", quote(&seed.to_string())));
+        }
+        if let Some(tropism) = self.tropism {
+            value.push_str(&format!("tropism = {}\n", quote(&tropism.to_string())));
+        }
+        if let Some(region) = &self.region {
+            value.push_str(&format!("region = {}\n", quote(&region.to_string())));
+        }
+        value.push_str(&format!(
+            "axiom = {}\n\n[rules]\n",
+            quote(&symbols_to_string(&self.axiom))
+        ));
+        for (head, rule) in self.rule_entries() {
+            value.push_str(&format!(
+                "{} = {}\n",
+                quote(&head),
+                quote(&symbols_to_string(rule))
+            ));
+        }
+        if !self.interpretations.is_empty() {
+            value.push_str("\n[interpret]\n");
+        }
+        for (symbol, interpretation) in self.sorted_interpretations() {
+            value.push_str(&format!(
+                "{} = {}\n",
+                quote(&symbol.to_string()),
+                quote(&symbols_to_string(interpretation))
+            ));
+        }
+        if !self.draws.is_empty() {
+            value.push_str("\n[draw]\n");
+        }
+        for (symbol, command) in self.sorted_draws() {
+            value.push_str(&format!(
+                "{} = {}\n",
+                quote(&symbol.to_string()),
+                quote(&command.to_string())
+            ));
+        }
+        value
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ParseError> {
+        TurtleGraphConfig::from_toml_in(text, Path::new(""))
+    }
+
+    /// `from_toml` for a file on `directory`.
+    pub fn from_toml_in(text: &str, directory: &Path) -> Result<Self, ParseError> {
+        let mut fields = Vec::new();
+        // Tables as they are opened, the fields after a header go to the last one.
+        let mut tables: Vec<(String, usize, Fields)> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                let header = line.split('#').next().map(str::trim).unwrap_or("");
+                let name = match header {
+                    "[rules]" => "rules",
+                    "[draw]" => "draw",
+                    "[interpret]" => "interpret",
+                    _ => {
+                        return Err(ParseError {
+                            line: i + 1,
+                            message: format!("unknown table `{}`", line),
+                        })
+                    }
+                };
+                tables.push((name.into(), i + 1, Vec::new()));
+                continue;
+            }
+            let (key, value) = Cursor::new(line, i + 1).toml_field()?;
+            match tables.last_mut() {
+                Some((_, _, table)) => table.push((key, i + 1, value)),
+                None => fields.push((key, i + 1, value)),
+            }
+        }
+        for (name, line, table) in tables {
+            fields.push((name, line, Value::Table(table)));
+        }
+        config_from_fields(fields, directory)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    /// Every grammar of `grammar/` with its file name.
+    fn grammars() -> Vec<(String, TurtleGraphConfig)> {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("grammar");
+        let mut grammars: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().map(|e| e == "lsy").unwrap_or(false))
+            .map(|path| {
+                let text = std::fs::read_to_string(&path).unwrap();
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                let config = TurtleGraphConfig::from_str(&text)
+                    .unwrap_or_else(|error| panic!("{}: {}", name, error));
+                (name, config)
+            })
+            .collect();
+        grammars.sort_by(|a, b| a.0.cmp(&b.0));
+        assert!(!grammars.is_empty());
+        grammars
+    }
+
+    fn without_comments(config: &TurtleGraphConfig) -> TurtleGraphConfig {
+        let mut config = config.clone();
+        config.comments = Default::default();
+        config
+    }
+
+    #[test]
+    fn lsy_round_trip() {
+        for (name, config) in grammars() {
+            let printed = config.to_string();
+            let parsed = TurtleGraphConfig::from_str(&printed).unwrap();
+            assert_eq!(parsed, config, "{}", name);
+            assert_eq!(parsed.to_string(), printed, "{}", name);
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        for (name, config) in grammars() {
+            let parsed = TurtleGraphConfig::from_json(&config.to_json())
+                .unwrap_or_else(|error| panic!("{}: {}", name, error));
+            assert_eq!(parsed, without_comments(&config), "{}", name);
+        }
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        for (name, config) in grammars() {
+            let parsed = TurtleGraphConfig::from_toml(&config.to_toml())
+                .unwrap_or_else(|error| panic!("{}: {}", name, error));
+            assert_eq!(parsed, without_comments(&config), "{}", name);
+        }
+    }
+
+    #[test]
+    fn comments() {
+        let config = TurtleGraphConfig::from_str(
+            "; Preamble\n  angle   8 ;means 360/8\n\n;rules\nX  = [-FX]+FX\n; end",
+        )
+        .unwrap();
+        assert_eq!(
+            config.to_string(),
+            "; Preamble\nangle 8 ; means 360/8\n; rules\nX = [-FX]+FX\n; end\n"
+        );
+    }
+
+    #[test]
+    fn formats() {
+        let config = TurtleGraphConfig::from_str("angle 6\naxiom F\"\nF = F+F\n").unwrap();
+        assert_eq!(
+            config.to_json(),
+            "{\n  \"angle\": 6,\n  \"axiom\": \"F\\\"\",\n  \"rules\": {\n    \"F\": \"F+F\"\n  }\n}\n"
+        );
+        assert_eq!(
+            config.to_toml(),
+            "angle = 6\naxiom = \"F\\\"\"\n\n[rules]\n\"F\" = \"F+F\"\n"
+        );
+    }
+
+    #[test]
+    fn draws() {
+        let config =
+            TurtleGraphConfig::from_str("draw {leaf} G\naxiom {Fl}{leaf}\n{Fl} = {Fl}{Fr}\n")
+                .unwrap();
+        assert_eq!(
+            config.to_json(),
+            "{\n  \"draw\": {\n    \"{leaf}\": \"G\"\n  },\n  \"axiom\": \"{Fl}{leaf}\",\n  \"rules\": {\n    \"{Fl}\": \"{Fl}{Fr}\"\n  }\n}\n"
+        );
+        assert_eq!(
+            config.to_toml(),
+            "axiom = \"{Fl}{leaf}\"\n\n[rules]\n\"{Fl}\" = \"{Fl}{Fr}\"\n\n[draw]\n\"{leaf}\" = \"G\"\n"
+        );
+        assert_eq!(
+            TurtleGraphConfig::from_json(&config.to_json()).unwrap(),
+            config
+        );
+        assert_eq!(
+            TurtleGraphConfig::from_toml(&config.to_toml()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn seed() {
+        let config = TurtleGraphConfig::from_str(&format!("seed {}\naxiom F", u64::MAX)).unwrap();
+        assert!(config
+            .to_json()
+            .contains("\"seed\": \"18446744073709551615\""));
+        assert_eq!(
+            TurtleGraphConfig::from_json(&config.to_json()).unwrap(),
+            config
+        );
+        assert_eq!(
+            TurtleGraphConfig::from_toml(&config.to_toml()).unwrap(),
+            config
+        );
+        let written = TurtleGraphConfig::from_toml("seed = 7\n[rules]\n\"F \" = \"FF\"").unwrap();
+        assert_eq!(written.seed, Some(7));
+        assert_eq!(written.rules.len(), 1);
+        assert!(TurtleGraphConfig::from_json("{\"seed\": \"-1\"}").is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let error = TurtleGraphConfig::from_json("{\n  \"angle\": 300\n}").unwrap_err();
+        assert_eq!(error.line, 2);
+        let error = TurtleGraphConfig::from_toml("angle = 6\n[rules]\nFF = \"F\"").unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+}
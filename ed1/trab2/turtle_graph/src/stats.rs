@@ -0,0 +1,143 @@
+use crate::{serialize::quote, Colors, Point, TurtleSyntax};
+
+/// Numbers describing a drawing, taken from the turtle walk with the rotation of
+/// the grammar applied.
+#[derive(Debug)]
+pub struct Stats {
+    pub bounds: Option<(Point, Point)>,
+    /// Sum of the lengths of every segment drawn.
+    pub length: f64,
+    /// Segments drawn with each color, in the order of their digits.
+    pub colors: Vec<(Colors, usize)>,
+    pub max_depth: usize,
+    pub tips: usize,
+    pub end: Point,
+    /// Heading at the end in degrees from 0 to 360.
+    pub heading: f32,
+}
+
+impl TurtleSyntax {
+    pub fn stats(&self) -> Stats {
+        let walk = self.walk();
+        let mut bounds: Option<(Point, Point)> = None;
+        let mut length = 0f64;
+        let mut colors: Vec<(Colors, usize)> = ('0'..='9')
+            .filter_map(Colors::from_digit)
+            .map(|color| (color, 0))
+            .collect();
+        for segment in &walk.segments {
+            let (dx, dy) = (segment.to.x - segment.from.x, segment.to.y - segment.from.y);
+            length += (dx as f64).hypot(dy as f64);
+            if let Some((_, count)) = colors.iter_mut().find(|(c, _)| *c == segment.color) {
+                *count += 1;
+            }
+            for point in &[segment.from, segment.to] {
+                let point = point.rotated(self.rotate);
+                bounds = Some(match bounds {
+                    None => (point, point),
+                    Some((min, max)) => (
+                        Point::new(min.x.min(point.x), min.y.min(point.y)),
+                        Point::new(max.x.max(point.x), max.y.max(point.y)),
+                    ),
+                });
+            }
+        }
+        colors.retain(|(_, count)| *count > 0);
+        Stats {
+            bounds,
+            length,
+            colors,
+            max_depth: walk.max_depth,
+            tips: walk.tips,
+            end: walk.end.rotated(self.rotate),
+            heading: (walk.heading + self.rotate).rem_euclid(360.0),
+        }
+    }
+}
+
+impl Stats {
+    pub fn segments(&self) -> usize {
+        self.colors.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn table(&self) -> String {
+        let mut value = String::new();
+        if let Some((min, max)) = self.bounds {
+            value.push_str(&format!(
+                "bounds: {:.2} {:.2} {:.2} {:.2}\n",
+                min.x, min.y, max.x, max.y
+            ));
+        }
+        value.push_str(&format!("length: {:.2}\n", self.length));
+        value.push_str(&format!("segments: {}\n", self.segments()));
+        for (color, count) in &self.colors {
+            value.push_str(&format!("segments {:?}: {}\n", color, count));
+        }
+        value.push_str(&format!("max depth: {}\n", self.max_depth));
+        value.push_str(&format!("branch tips: {}\n", self.tips));
+        value.push_str(&format!(
+            "end: {:.2} {:.2} heading {:.2}\n",
+            self.end.x, self.end.y, self.heading
+        ));
+        value
+    }
+
+    pub fn to_json(&self) -> String {
+        let bounds = match self.bounds {
+            Some((min, max)) => format!("[{:.2}, {:.2}, {:.2}, {:.2}]", min.x, min.y, max.x, max.y),
+            None => "null".into(),
+        };
+        let colors: Vec<String> = self
+            .colors
+            .iter()
+            .map(|(color, count)| format!("{}: {}", quote(&format!("{:?}", color)), count))
+            .collect();
+        let fields = [
+            format!("  \"bounds\": {}", bounds),
+            format!("  \"length\": {:.2}", self.length),
+            format!("  \"segments\": {}", self.segments()),
+            format!("  \"colors\": {{{}}}", colors.join(", ")),
+            format!("  \"max_depth\": {}", self.max_depth),
+            format!("  \"tips\": {}", self.tips),
+            format!(
+                "  \"end\": {{\"x\": {:.2}, \"y\": {:.2}, \"heading\": {:.2}}}",
+                self.end.x, self.end.y, self.heading
+            ),
+        ];
+        format!("{{\n{}\n}}\n", fields.join(",\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::TurtleGraphConfig;
+
+    #[test]
+    fn tree() {
+        let syntax = TurtleGraphConfig::from_str("angle 4\naxiom F[+F[-F]C1F]F[G]+")
+            .unwrap()
+            .generate_syntax();
+        assert_eq!(
+            syntax.stats().table(),
+            "bounds: 0.00 0.00 200.00 200.00\nlength: 500.00\nsegments: 5\n\
+             segments Black: 4\nsegments Red: 1\nmax depth: 2\nbranch tips: 2\n\
+             end: 200.00 0.00 heading 90.00\n"
+        );
+        let json = syntax.stats().to_json();
+        assert!(json.contains("  \"colors\": {\"Black\": 4, \"Red\": 1},\n"));
+        assert!(json.contains("  \"end\": {\"x\": 200.00, \"y\": 0.00, \"heading\": 90.00}\n"));
+    }
+
+    #[test]
+    fn rotated() {
+        let stats = TurtleGraphConfig::from_str("rotate 90\naxiom GG")
+            .unwrap()
+            .generate_syntax()
+            .stats();
+        assert_eq!(stats.bounds, None);
+        assert_eq!(stats.heading, 90.0);
+        assert!(stats.end.x.abs() < 1e-3 && (stats.end.y - 200.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,148 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use crate::TurtleSymbol;
+
+/// Longest name of a symbol written between braces, in bytes.
+pub const NAME_LENGTH: usize = 15;
+
+/// Name of a symbol longer than a char, kept inline so symbols stay `Copy`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Name {
+    bytes: [u8; NAME_LENGTH],
+    len: u8,
+}
+
+impl Name {
+    pub fn new(name: &str) -> Option<Self> {
+        if name.is_empty() || name.len() > NAME_LENGTH {
+            return None;
+        }
+        let mut bytes = [0; NAME_LENGTH];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        Some(Self {
+            bytes,
+            len: name.len() as u8,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or("")
+    }
+}
+
+impl Debug for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+/// Reads the symbol at the start of `s`, returning the rest. A name between braces
+/// like `{leaf}` or `{Fl}` is a single symbol, any other char is one on its own.
+/// `?P(x,y)` is the query `?` with the position its rules may ask for spelled out.
+pub fn parse_symbol(s: &str) -> Result<Option<(TurtleSymbol, &str)>, String> {
+    let mut chars = s.chars();
+    let c = match chars.next() {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+    if c == '{' {
+        let end = s
+            .find('}')
+            .ok_or_else(|| format!("`{}` has no closing `}}`", s))?;
+        let name = &s[1..end];
+        if name.contains(|c: char| c.is_whitespace() || c == '{') {
+            return Err(format!("invalid symbol name `{}`", name));
+        }
+        let mut name_chars = name.chars();
+        let symbol = match (name_chars.next(), name_chars.next()) {
+            (Some(c), None) => TurtleSymbol::from(c),
+            _ => TurtleSymbol::Named(Name::new(name).ok_or_else(|| {
+                format!(
+                    "symbol name `{}` must have from 1 to {} bytes",
+                    name, NAME_LENGTH
+                )
+            })?),
+        };
+        return Ok(Some((symbol, &s[end + 1..])));
+    }
+    if let Some(rest) = s.strip_prefix("?P(x,y)") {
+        return Ok(Some((TurtleSymbol::Query, rest)));
+    }
+    Ok(Some((TurtleSymbol::from(c), chars.as_str())))
+}
+
+/// Splits a whole axiom or production into symbols.
+pub fn parse_symbols(mut s: &str) -> Result<Vec<TurtleSymbol>, String> {
+    let mut symbols = Vec::new();
+    while let Some((symbol, rest)) = parse_symbol(s)? {
+        symbols.push(symbol);
+        s = rest;
+    }
+    Ok(symbols)
+}
+
+impl TurtleSymbol {
+    /// The turtle command the symbol runs, as assigned by `draws`. Symbols without
+    /// one only stand for themselves.
+    pub fn command(&self, draws: &HashMap<TurtleSymbol, TurtleSymbol>) -> TurtleSymbol {
+        // Most grammars draw nothing else, skipping the hashing of every symbol.
+        let draw = if draws.is_empty() {
+            None
+        } else {
+            draws.get(self)
+        };
+        draw.copied().unwrap_or(*self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{symbols_to_string, TurtleGraphConfig};
+
+    #[test]
+    fn tokens() {
+        let symbols = parse_symbols("{Fl}+{leaf}F{A}{Fr}").unwrap();
+        assert_eq!(symbols.len(), 6);
+        assert_eq!(symbols[4], TurtleSymbol::from('A'));
+        assert_eq!(symbols_to_string(&symbols), "{Fl}+{leaf}FA{Fr}");
+        // Names are only taken between braces, `l` after `F` is a symbol of its own.
+        assert_eq!(
+            parse_symbols("Flr").unwrap(),
+            vec![
+                TurtleSymbol::F,
+                TurtleSymbol::from('l'),
+                TurtleSymbol::from('r')
+            ]
+        );
+        assert!(parse_symbols("F{leaf").is_err());
+        assert!(parse_symbols("{}").is_err());
+        assert!(parse_symbols("{a_very_long_symbol_name}").is_err());
+
+        let mut draws = HashMap::new();
+        assert_eq!(symbols[0].command(&draws), symbols[0]);
+        assert_eq!(symbols[2].command(&draws), symbols[2]);
+        draws.insert(symbols[0], TurtleSymbol::G);
+        draws.insert(symbols[2], TurtleSymbol::F);
+        assert_eq!(symbols[0].command(&draws), TurtleSymbol::G);
+        assert_eq!(symbols[2].command(&draws), TurtleSymbol::F);
+    }
+
+    #[test]
+    fn edges() {
+        let rules = "axiom {stem}{Fl}\n{Fl} = {Fl}+{Fr}++{Fr}-{Fl}--{Fl}{Fl}-{Fr}+\n{Fr} = -{Fl}+{Fr}{Fr}++{Fr}+{Fl}--{Fl}-{Fr}\n";
+        let text = format!(
+            "angle 6\norder 2\ndraw {{Fl}} F\ndraw {{Fr}} F\ndraw {{stem}} F\n{}",
+            rules
+        );
+        let config = TurtleGraphConfig::from_str(&text).unwrap();
+        assert_eq!(config.to_string(), text);
+        assert_eq!(config.generate_syntax().segments().len(), 50);
+        assert_eq!(config.growth().segments(2).to_string(), "50");
+        // Names only draw when told to, whatever they look like.
+        let config = TurtleGraphConfig::from_str(&format!("order 2\n{}", rules)).unwrap();
+        assert!(config.generate_syntax().segments().is_empty());
+    }
+}
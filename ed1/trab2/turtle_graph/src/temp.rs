@@ -0,0 +1,52 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Directories made so far by this process, to keep their names apart.
+static MADE: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory under the temporary one only used by a test, named after the
+/// process so runs at the same time do not share it, and removed when dropped
+/// even if the test fails.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "turtle_graph_{}_{}_{}",
+            name,
+            std::process::id(),
+            MADE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn removed_when_dropped() {
+        let first = TempDir::new("temp");
+        let second = TempDir::new("temp");
+        assert_ne!(first.path(), second.path());
+        assert!(first.path().is_dir());
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+    }
+}
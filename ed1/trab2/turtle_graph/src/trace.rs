@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use crate::{Branch, Tree, TurtleGraphConfig, TurtleSymbol};
+
+/// Longest string printed for each generation, longer ones are cut and counted.
+const TRACE_SYMBOLS: usize = 200;
+
+/// Most lines printed by a diff.
+const DIFF_LINES: usize = 200;
+
+/// Symbols `branch` was rewritten into on `generation`, if its rule fired then.
+fn rewritten<'a>(
+    tree: &'a Tree<TurtleSymbol>,
+    branch: &Branch<TurtleSymbol>,
+    generation: usize,
+) -> Option<&'a [Branch<TurtleSymbol>]> {
+    let children = tree.children(branch);
+    match children.first() {
+        Some(child) if child.generation == generation => Some(children),
+        _ => None,
+    }
+}
+
+fn branches_to_string(branches: &[&Branch<TurtleSymbol>]) -> String {
+    branches
+        .iter()
+        .map(|branch| branch.data.to_string())
+        .collect()
+}
+
+/// `mark` cut or padded to the printed width of `symbol`, so the marks stay under
+/// their symbols when some are longer than a char.
+fn mark_under(mark: &str, symbol: &TurtleSymbol) -> String {
+    let width = symbol.to_string().chars().count();
+    format!("{:width$.width$}", mark, width = width)
+}
+
+impl TurtleGraphConfig {
+    /// Every generation with a line marking under each symbol the rule that wrote it,
+    /// how many times each rule fired and how many symbols were cut.
+    pub fn trace(&self) -> String {
+        let tree = self.derivation_tree();
+        let mut value = String::new();
+        for generation in 0..=tree.generations() {
+            let branches = tree.generation(generation);
+            value.push_str(&format!(
+                "generation {} ({} symbols)\n",
+                generation,
+                branches.len()
+            ));
+            let shown = &branches[..branches.len().min(TRACE_SYMBOLS)];
+            let cut = if shown.len() < branches.len() {
+                "..."
+            } else {
+                ""
+            };
+            value.push_str(&format!("  {}{}\n", branches_to_string(shown), cut));
+            if generation == 0 {
+                continue;
+            }
+
+            let marks: String = shown
+                .iter()
+                .map(|branch| {
+                    let mark = match branch.parent {
+                        Some(parent) if branch.generation == generation => {
+                            tree.get(parent).data.to_string()
+                        }
+                        _ => String::new(),
+                    };
+                    mark_under(&mark, &branch.data)
+                })
+                .collect();
+            if !marks.trim().is_empty() {
+                value.push_str(&format!("  {}\n", marks.trim_end()));
+            }
+            let mut fired: BTreeMap<String, usize> = BTreeMap::new();
+            for branch in tree.generation(generation - 1) {
+                if rewritten(&tree, branch, generation).is_some() {
+                    *fired.entry(branch.data.to_string()).or_insert(0) += 1;
+                }
+            }
+            let fired: Vec<String> = fired
+                .iter()
+                .map(|(symbol, count)| format!("{} x{}", symbol, count))
+                .collect();
+            if fired.is_empty() {
+                value.push_str("  no rule fired\n");
+            } else {
+                value.push_str(&format!("  fired: {}\n", fired.join(", ")));
+            }
+            let removed = tree.removed(generation);
+            if removed > 0 {
+                value.push_str(&format!("  cut: {} symbols\n", removed));
+            }
+        }
+        value
+    }
+
+    /// What became of each symbol of `generation` on the next one, runs of symbols
+    /// without a rule are listed together. `None` past the last generation.
+    pub fn diff(&self, generation: usize) -> Option<String> {
+        let tree = self.derivation_tree();
+        if generation >= tree.generations() {
+            return None;
+        }
+        let mut lines = Vec::new();
+        let mut unchanged: Vec<&Branch<TurtleSymbol>> = Vec::new();
+        let mut start = 0;
+        let flush = |lines: &mut Vec<String>, unchanged: &mut Vec<&Branch<TurtleSymbol>>, start| {
+            if !unchanged.is_empty() {
+                let shown = &unchanged[..unchanged.len().min(TRACE_SYMBOLS)];
+                let cut = if shown.len() < unchanged.len() {
+                    "..."
+                } else {
+                    ""
+                };
+                lines.push(format!(
+                    "{}: {}{} unchanged",
+                    start,
+                    branches_to_string(shown),
+                    cut
+                ));
+                unchanged.clear();
+            }
+        };
+        for (i, branch) in tree.generation(generation).into_iter().enumerate() {
+            match rewritten(&tree, branch, generation + 1) {
+                Some(children) => {
+                    flush(&mut lines, &mut unchanged, start);
+                    let children: Vec<&Branch<TurtleSymbol>> = children.iter().collect();
+                    lines.push(format!(
+                        "{}: {} -> {}",
+                        i,
+                        branch.data,
+                        branches_to_string(&children)
+                    ));
+                }
+                None => {
+                    if unchanged.is_empty() {
+                        start = i;
+                    }
+                    unchanged.push(branch);
+                }
+            }
+        }
+        flush(&mut lines, &mut unchanged, start);
+
+        let mut value = format!("generation {} -> {}\n", generation, generation + 1);
+        for line in lines.iter().take(DIFF_LINES) {
+            value.push_str(&format!("  {}\n", line));
+        }
+        if lines.len() > DIFF_LINES {
+            value.push_str(&format!("  ... ({} more)\n", lines.len() - DIFF_LINES));
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const PLANT: &str = "order 2\naxiom X\nX = F[X]Y\nY = FX";
+
+    #[test]
+    fn trace() {
+        let config = TurtleGraphConfig::from_str(PLANT).unwrap();
+        assert_eq!(
+            config.trace(),
+            "generation 0 (1 symbols)\n  X\n\
+             generation 1 (5 symbols)\n  F[X]Y\n  XXXXX\n  fired: X x1\n\
+             generation 2 (10 symbols)\n  F[F[X]Y]FX\n   \
+             \x20XXXXX YY\n  fired: X x1, Y x1\n"
+        );
+    }
+
+    #[test]
+    fn named_symbols() {
+        let config =
+            TurtleGraphConfig::from_str("order 2\naxiom {leaf}\n{leaf} = F{leaf}\nF = {Fl}")
+                .unwrap();
+        assert_eq!(
+            config.trace(),
+            "generation 0 (1 symbols)\n  {leaf}\n\
+             generation 1 (2 symbols)\n  F{leaf}\n  {{leaf}\n  fired: {leaf} x1\n\
+             generation 2 (3 symbols)\n  {Fl}F{leaf}\n  F   {{leaf}\n  fired: F x1, {leaf} x1\n"
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let config = TurtleGraphConfig::from_str(PLANT).unwrap();
+        assert_eq!(
+            config.diff(1).unwrap(),
+            "generation 1 -> 2\n  0: F[ unchanged\n  2: X -> F[X]Y\n  3: ] unchanged\n  4: Y -> FX\n"
+        );
+        assert_eq!(config.diff(2), None);
+    }
+}
@@ -1,12 +1,314 @@
-//use std::fmt::Debug;
-//
-//pub struct Tree<T>
-//where
-//    T: Debug, {}
-//
-//pub struct Branch<T>
-//where
-//    T: Debug, {
-//
-//    }
-//
+use std::{fmt::Debug, ops::Range};
+
+use crate::{cut::Cutter, symbols_to_string, TurtleGraphConfig, TurtleSymbol, TurtleSyntax};
+
+/// Colors given to each generation by `generation_syntax`, white is left out since
+/// it does not show on the page.
+const GENERATION_COLORS: [char; 6] = ['0', '1', '2', '3', '4', '5'];
+
+/// Every symbol written while deriving a string, each one linked to the symbol its
+/// rule rewrote so the derivation can be followed back to the axiom.
+#[derive(Debug)]
+pub struct Tree<T>
+where
+    T: Debug,
+{
+    branches: Vec<Branch<T>>,
+    /// Branches of the current string, in order.
+    leaves: Vec<usize>,
+    generations: usize,
+}
+
+#[derive(Debug)]
+pub struct Branch<T>
+where
+    T: Debug,
+{
+    pub data: T,
+    /// Generation that wrote the symbol, 0 for the axiom.
+    pub generation: usize,
+    /// The symbol whose rule wrote this one.
+    pub parent: Option<usize>,
+    /// Children of a branch are written together, so they are a range of the tree.
+    children: Range<usize>,
+    /// Generation whose rule rewrote the symbol, its children may be none at all.
+    rewritten: Option<usize>,
+    /// Generation that removed the symbol from the string, if any.
+    pub removed: Option<usize>,
+}
+
+impl<T> Tree<T>
+where
+    T: Debug + Clone,
+{
+    pub fn new(axiom: impl IntoIterator<Item = T>) -> Self {
+        let branches: Vec<Branch<T>> = axiom
+            .into_iter()
+            .map(|data| Branch {
+                data,
+                generation: 0,
+                parent: None,
+                children: 0..0,
+                rewritten: None,
+                removed: None,
+            })
+            .collect();
+        Self {
+            leaves: (0..branches.len()).collect(),
+            branches,
+            generations: 0,
+        }
+    }
+
+    /// Rewrites every leaf with a production at once, leaves without one stay
+    /// as they are and keep the generation that wrote them.
+    /// `production` gets each symbol, in order, with the steps it went through
+    /// unchanged.
+    pub fn derive<'a>(&mut self, mut production: impl FnMut(&T, usize) -> Option<&'a [T]>)
+    where
+        T: 'a,
+    {
+        let time = self.generations;
+        self.generations += 1;
+        let mut leaves = Vec::with_capacity(self.leaves.len());
+        for &leaf in &self.leaves {
+            let branch = &self.branches[leaf];
+            let value = match production(&branch.data, time - branch.generation) {
+                Some(value) => value,
+                None => {
+                    leaves.push(leaf);
+                    continue;
+                }
+            };
+            let start = self.branches.len();
+            for data in value {
+                leaves.push(self.branches.len());
+                self.branches.push(Branch {
+                    data: data.clone(),
+                    generation: self.generations,
+                    parent: Some(leaf),
+                    children: 0..0,
+                    rewritten: None,
+                    removed: None,
+                });
+            }
+            self.branches[leaf].children = start..self.branches.len();
+            self.branches[leaf].rewritten = Some(self.generations);
+        }
+        self.leaves = leaves;
+    }
+
+    /// Takes the leaves `keep` rejects out of the current string, visiting them in
+    /// order. They stay on the tree marked as removed by the last generation.
+    pub fn retain_leaves(&mut self, mut keep: impl FnMut(&Branch<T>) -> bool) {
+        let branches = &mut self.branches;
+        let generation = self.generations;
+        self.leaves.retain(|&leaf| {
+            let kept = keep(&branches[leaf]);
+            if !kept {
+                branches[leaf].removed = Some(generation);
+            }
+            kept
+        });
+    }
+
+    /// Number of symbols `generation` took out of the string.
+    pub fn removed(&self, generation: usize) -> usize {
+        self.branches
+            .iter()
+            .filter(|branch| branch.removed == Some(generation))
+            .count()
+    }
+
+    /// Number of times `derive` ran.
+    pub fn generations(&self) -> usize {
+        self.generations
+    }
+
+    pub fn get(&self, index: usize) -> &Branch<T> {
+        &self.branches[index]
+    }
+
+    /// Symbols the rule of `branch` wrote, empty while it was not rewritten.
+    pub fn children(&self, branch: &Branch<T>) -> &[Branch<T>] {
+        &self.branches[branch.children.clone()]
+    }
+
+    /// Indices of the branches making the current string.
+    pub fn leaves(&self) -> &[usize] {
+        &self.leaves
+    }
+
+    /// The branch at `index` followed by its parent, up to a symbol of the axiom.
+    pub fn ancestors(&self, index: usize) -> Vec<&Branch<T>> {
+        let mut value = Vec::new();
+        let mut current = Some(index);
+        while let Some(index) = current {
+            value.push(&self.branches[index]);
+            current = self.branches[index].parent;
+        }
+        value
+    }
+
+    /// The string as it was after `generation` derivations.
+    pub fn generation(&self, generation: usize) -> Vec<&Branch<T>> {
+        let mut value = Vec::new();
+        let roots = self.branches.iter().take_while(|b| b.parent.is_none());
+        let mut stack: Vec<usize> = (0..roots.count()).rev().collect();
+        while let Some(index) = stack.pop() {
+            let branch = &self.branches[index];
+            if matches!(branch.removed, Some(removed) if removed <= generation) {
+                continue;
+            }
+            if matches!(branch.rewritten, Some(rewritten) if rewritten <= generation) {
+                stack.extend(branch.children.clone().rev());
+            } else {
+                value.push(branch);
+            }
+        }
+        value
+    }
+}
+
+impl TurtleGraphConfig {
+    /// Derives the axiom keeping track of where every symbol came from.
+    pub fn derivation_tree(&self) -> Tree<TurtleSymbol> {
+        let mut tree = Tree::new(self.axiom.iter().copied());
+        let prunes = self.prunes();
+        let syntax = self.axiom_syntax();
+        for time in 0..self.order.unwrap_or(0) as usize {
+            let positions = match self.queries_position() {
+                true => {
+                    let leaves = tree.leaves().iter().map(|&leaf| tree.get(leaf).data);
+                    self.query_positions(leaves)
+                }
+                false => Vec::new(),
+            };
+            let mut positions = positions.into_iter();
+            tree.derive(|symbol, age| {
+                let position = match symbol {
+                    TurtleSymbol::Query => positions.next(),
+                    _ => None,
+                };
+                self.production(symbol, time, age, position)
+            });
+            if !prunes {
+                continue;
+            }
+            // The symbols stay as written, a `?` outside the region is only cut.
+            let leaves = tree.leaves().iter().map(|&leaf| tree.get(leaf).data);
+            let outside = match &self.region {
+                Some(region) => syntax.outside(region, leaves),
+                None => Vec::new(),
+            };
+            let mut outside = outside.into_iter();
+            let mut cutter = Cutter::default();
+            tree.retain_leaves(|branch| match branch.data {
+                TurtleSymbol::Query if outside.next() == Some(true) => {
+                    cutter.keep(&TurtleSymbol::Cut)
+                }
+                symbol => cutter.keep(&symbol),
+            });
+        }
+        tree
+    }
+
+    /// The interpreted expansion with every `F`, or symbol drawn like it, colored by
+    /// the generation that wrote the symbol it comes from.
+    pub fn generation_syntax(&self) -> TurtleSyntax {
+        let tree = self.derivation_tree();
+        let mut syntax = self.axiom_syntax();
+        syntax.list = tree
+            .leaves()
+            .iter()
+            .map(|&leaf| tree.get(leaf))
+            .flat_map(|branch| {
+                let color = GENERATION_COLORS[branch.generation % GENERATION_COLORS.len()];
+                let value = match self.interpretations.get(&branch.data) {
+                    Some(value) => value.as_slice(),
+                    None => std::slice::from_ref(&branch.data),
+                };
+                value
+                    .iter()
+                    .flat_map(|symbol| match symbol.command(&self.draws) {
+                        TurtleSymbol::F => vec![
+                            TurtleSymbol::CustomSymbol('C'),
+                            TurtleSymbol::CustomSymbol(color),
+                            *symbol,
+                        ],
+                        _ => vec![*symbol],
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        syntax
+    }
+
+    /// The string of every generation, one per line.
+    pub fn derivation(&self) -> String {
+        let tree = self.derivation_tree();
+        let mut value = String::new();
+        for generation in 0..=tree.generations() {
+            let symbols: Vec<TurtleSymbol> = tree
+                .generation(generation)
+                .iter()
+                .map(|branch| branch.data)
+                .collect();
+            value.push_str(&format!(
+                "{}: {}\n",
+                generation,
+                symbols_to_string(&symbols)
+            ));
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn derivation() {
+        let config = TurtleGraphConfig::from_str("order 3\naxiom X\nX = F[X]Y\nY = FX").unwrap();
+        let tree = config.derivation_tree();
+        let leaves: Vec<TurtleSymbol> = tree.leaves().iter().map(|&i| tree.get(i).data).collect();
+        assert_eq!(
+            symbols_to_string(&leaves),
+            config.generate_syntax().string()
+        );
+        assert_eq!(
+            config.derivation(),
+            "0: X\n1: F[X]Y\n2: F[F[X]Y]FX\n3: F[F[F[X]Y]FX]FF[X]Y\n"
+        );
+
+        let last = tree.get(*tree.leaves().last().unwrap());
+        assert_eq!(last.generation, 3);
+        let ancestors: Vec<String> = tree
+            .ancestors(*tree.leaves().last().unwrap())
+            .iter()
+            .map(|branch| format!("{}{}", branch.data, branch.generation))
+            .collect();
+        assert_eq!(ancestors, vec!["Y3", "X2", "Y1", "X0"]);
+        assert_eq!(tree.get(tree.leaves()[0]).generation, 1);
+    }
+
+    #[test]
+    fn empty_rule() {
+        let config = TurtleGraphConfig::from_str("order 2\naxiom FXF\nX = \nF = FX").unwrap();
+        assert_eq!(config.derivation(), "0: FXF\n1: FXFX\n2: FXFX\n");
+        let tree = config.derivation_tree();
+        assert!(tree.children(tree.get(1)).is_empty());
+        assert_eq!(config.generation_syntax().string(), "C2FXC2FX");
+    }
+
+    #[test]
+    fn generation_syntax() {
+        let config = TurtleGraphConfig::from_str("order 2\naxiom F\nF = FG").unwrap();
+        assert_eq!(config.generation_syntax().string(), "C2FGG");
+        let config = TurtleGraphConfig::from_str("order 2\naxiom X\nX = F[X]").unwrap();
+        assert_eq!(config.generation_syntax().string(), "C1F[C2F[X]]");
+    }
+}
@@ -0,0 +1,80 @@
+use std::{fmt::Display, str::FromStr};
+
+/// A force like gravity or light bending the heading of the turtle after every
+/// `F`, written `tropism {x} {y} {susceptibility}`. Only the plane is covered:
+/// the turtle has no 3D heading to rotate around `H x T`, so the vector has two
+/// coordinates and the bend is a turn of the heading angle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tropism {
+    pub x: f32,
+    pub y: f32,
+    pub susceptibility: f32,
+}
+
+impl FromStr for Tropism {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<f32> = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| ())?;
+        match values.as_slice() {
+            [x, y, susceptibility] => Ok(Self {
+                x: *x,
+                y: *y,
+                susceptibility: *susceptibility,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Tropism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.x, self.y, self.susceptibility)
+    }
+}
+
+impl Tropism {
+    /// The vector as seen by a turtle whose drawing is rotated by `degrees`, so it
+    /// keeps pointing the same way on the page.
+    pub fn unrotated(&self, degrees: f32) -> Self {
+        let (sin, cos) = (-degrees).to_radians().sin_cos();
+        Self {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+            susceptibility: self.susceptibility,
+        }
+    }
+
+    /// The heading `angle`, in radians, turned toward the tropism vector by the
+    /// susceptibility times `|H x T|` as on The Algorithmic Beauty of Plants.
+    pub fn bend(&self, angle: f32) -> f32 {
+        let (sin, cos) = angle.sin_cos();
+        angle + self.susceptibility * (cos * self.y - sin * self.x)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    #[test]
+    fn bend() {
+        let gravity: Tropism = "0 -1 0.5".parse().unwrap();
+        assert_eq!(gravity.to_string(), "0 -1 0.5");
+        assert!("0 -1".parse::<Tropism>().is_err());
+        // Going right the heading turns down, going down it stays.
+        assert!((gravity.bend(0.0) + 0.5).abs() < 1e-6);
+        assert!((gravity.bend(-FRAC_PI_2) + FRAC_PI_2).abs() < 1e-6);
+        // Going up it is unstable but slowly falls to either side.
+        assert!(gravity.bend(FRAC_PI_2 + 0.1) > FRAC_PI_2 + 0.1);
+        // With the drawing turned up, down on the page is left for the turtle.
+        let turned = gravity.unrotated(90.0);
+        assert!((turned.x + 1.0).abs() < 1e-6 && turned.y.abs() < 1e-6);
+    }
+}
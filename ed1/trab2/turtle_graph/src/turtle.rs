@@ -1,16 +1,76 @@
 use std::{
-    collections::HashMap, convert::Infallible, f32::consts::TAU, fmt::Display, str::FromStr,
+    collections::HashMap,
+    f32::consts::TAU,
+    fmt::{Display, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use crate::List;
+use crate::{
+    cut::Cutter,
+    optimize,
+    parallel::{threads, Motion},
+    parse_symbol, parse_symbols, ConditionalRule, List, Name, Point, Random, Region, Segment,
+    Tropism, DEFAULT_SEED,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct TurtleGraphConfig {
-    angle: Option<u8>,
-    order: Option<u8>,
-    rotate: Option<i32>,
-    axiom: Vec<TurtleSymbol>,
-    rules: HashMap<TurtleSymbol, Vec<TurtleSymbol>>,
+    pub(crate) angle: Option<u8>,
+    pub(crate) order: Option<u8>,
+    pub(crate) rotate: Option<i32>,
+    /// Percent of the angle of each turn varied at random.
+    pub(crate) angle_jitter: Option<u8>,
+    /// Percent of the length of each step varied at random.
+    pub(crate) length_jitter: Option<u8>,
+    pub(crate) seed: Option<u64>,
+    pub(crate) tropism: Option<Tropism>,
+    /// Where branches may grow, `?` outside of it becomes `%`.
+    pub(crate) region: Option<Region>,
+    /// Turtle command run by each symbol, see `TurtleSymbol::command`.
+    pub(crate) draws: HashMap<TurtleSymbol, TurtleSymbol>,
+    pub(crate) axiom: Vec<TurtleSymbol>,
+    pub(crate) rules: HashMap<TurtleSymbol, Vec<TurtleSymbol>>,
+    /// Rules with a condition, in the order they were written.
+    pub(crate) conditional_rules: Vec<ConditionalRule>,
+    /// Replacements applied once after the last generation, turning the symbols of
+    /// the derivation into what the turtle draws.
+    pub(crate) interpretations: HashMap<TurtleSymbol, Vec<TurtleSymbol>>,
+    pub(crate) comments: Comments,
+    /// Directory the grammar was read from, `region polygon` files are relative to it.
+    pub(crate) directory: PathBuf,
+}
+
+/// A line of a grammar file that comments can be attached to.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Entry {
+    Angle,
+    Order,
+    Rotate,
+    AngleJitter,
+    LengthJitter,
+    Seed,
+    Tropism,
+    Region,
+    Draw(TurtleSymbol),
+    Axiom,
+    Rule(TurtleSymbol),
+    /// The conditional rule at this index.
+    ConditionalRule(usize),
+    Interpret(TurtleSymbol),
+    /// Comments after the last line of the grammar.
+    End,
+}
+
+/// Comments of a grammar file, kept so it can be written back with them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Comments {
+    /// Whole line comments above an entry.
+    pub(crate) above: HashMap<Entry, Vec<String>>,
+    /// Comments at the end of the line of an entry.
+    pub(crate) inline: HashMap<Entry, String>,
+    /// Whole line comments not attached to an entry yet.
+    pending: Vec<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -21,15 +81,33 @@ pub enum TurtleSymbol {
     Minus,
     PushStack,
     PopStack,
+    /// `?` or `?P(x,y)`, asks whether the turtle is inside the region of the grammar
+    /// and tells its rules where the turtle is.
+    Query,
+    /// `%`, removes the rest of its branch.
+    Cut,
     CustomSymbol(char),
+    /// A symbol longer than a char written between braces, like `{Fl}` or `{leaf}`.
+    Named(Name),
 }
 
 #[derive(Debug)]
 pub struct TurtleSyntax {
-    list: List<TurtleSymbol>,
+    pub(crate) list: List<TurtleSymbol>,
     angle: f32,
-    order: u8,
-    rotate: f32,
+    pub(crate) order: u8,
+    pub(crate) rotate: f32,
+    pub(crate) jitter: Option<Jitter>,
+    pub(crate) tropism: Option<Tropism>,
+    pub(crate) draws: HashMap<TurtleSymbol, TurtleSymbol>,
+}
+
+/// Random variation of the turtle commands, as fractions of their exact values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jitter {
+    pub angle: f32,
+    pub length: f32,
+    pub seed: u64,
 }
 
 impl From<char> for TurtleSymbol {
@@ -41,6 +119,8 @@ impl From<char> for TurtleSymbol {
             '-' => Self::Minus,
             '[' => Self::PushStack,
             ']' => Self::PopStack,
+            '?' => Self::Query,
+            '%' => Self::Cut,
             c => Self::CustomSymbol(c),
         }
     }
@@ -55,86 +135,447 @@ impl Display for TurtleSymbol {
             TurtleSymbol::Minus => '-',
             TurtleSymbol::PushStack => '[',
             TurtleSymbol::PopStack => ']',
+            TurtleSymbol::Query => '?',
+            TurtleSymbol::Cut => '%',
             TurtleSymbol::CustomSymbol(c) => *c,
+            TurtleSymbol::Named(name) => return write!(f, "{{{}}}", name.as_str()),
         };
         write!(f, "{}", c)
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn parse_value<T: FromStr>(word: &str, name: &str, line: usize) -> Result<Option<T>, ParseError> {
+    let value = word.trim_start_matches(name).trim();
+    str::parse(value).map(Some).map_err(|_| ParseError {
+        line,
+        message: format!("invalid value `{}` for {}", value, name),
+    })
+}
+
+/// Reads the `{symbol} {command}` of a `draw` line, the command being one of the
+/// moves of the turtle.
+pub(crate) fn parse_draw(s: &str) -> Option<(TurtleSymbol, TurtleSymbol)> {
+    let mut words = s.split_whitespace();
+    let symbol = match parse_symbol(words.next()?).ok()?? {
+        (symbol, "") => symbol,
+        _ => return None,
+    };
+    let command = match words.next()? {
+        "F" => TurtleSymbol::F,
+        "G" => TurtleSymbol::G,
+        "+" => TurtleSymbol::Plus,
+        "-" => TurtleSymbol::Minus,
+        _ => return None,
+    };
+    match words.next() {
+        Some(_) => None,
+        None => Some((symbol, command)),
+    }
+}
+
 impl FromStr for TurtleGraphConfig {
-    type Err = Infallible;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (angle, order, rotate, axiom, rules) =
-            s.lines()
-                .fold((None, None, None, None, HashMap::new()), |mut acc, line| {
-                    let line = line.trim();
-                    let line_end = line.find(';').unwrap_or(line.len());
-                    let word = &line[0..line_end];
-                    if word.starts_with("angle") {
-                        acc.0 = word
-                            .strip_prefix("angle")
-                            .map(|w| str::parse(w.trim()))
-                            .map(Result::ok)
-                            .flatten()
-                    } else if word.starts_with("order") {
-                        acc.1 = word
-                            .strip_prefix("order")
-                            .map(|w| str::parse(w.trim()))
-                            .map(Result::ok)
-                            .flatten()
-                    } else if word.starts_with("rotate") {
-                        acc.2 = word
-                            .strip_prefix("rotate")
-                            .map(|w| str::parse(w.trim()))
-                            .map(Result::ok)
-                            .flatten()
-                    } else if word.starts_with("axiom") {
-                        acc.3 = word
-                            .strip_prefix("axiom")
-                            .map(|w| w.trim().chars().map(TurtleSymbol::from).collect())
-                    } else {
-                        let rule = word.replace(" ", "");
-                        let mut chars = rule.chars();
-                        if let (Some(symbol), Some('=')) =
-                            (chars.next().map(TurtleSymbol::from), chars.next())
-                        {
-                            acc.4
-                                .insert(symbol, chars.map(TurtleSymbol::from).collect());
-                        }
-                    }
-                    acc
-                });
-        Ok(Self {
-            angle,
-            order,
-            rotate,
-            axiom: axiom.unwrap_or_default(),
-            rules,
-        })
+        TurtleGraphConfig::parse_in(s, Path::new(""))
     }
 }
 
 impl TurtleGraphConfig {
-    pub fn generate_syntax(&self) -> TurtleSyntax {
-        let mut syntax = TurtleSyntax {
+    /// Parses a grammar read from a file on `directory`.
+    pub fn parse_in(s: &str, directory: &Path) -> Result<Self, ParseError> {
+        let mut config = Self {
+            directory: directory.into(),
+            ..Self::default()
+        };
+        for (i, line) in s.lines().enumerate() {
+            config.parse_line(line, i + 1)?;
+        }
+        let pending = std::mem::take(&mut config.comments.pending);
+        if !pending.is_empty() {
+            config.comments.above.insert(Entry::End, pending);
+        }
+        Ok(config)
+    }
+}
+
+pub(crate) fn symbols_to_string(symbols: &[TurtleSymbol]) -> String {
+    symbols.iter().map(|symbol| symbol.to_string()).collect()
+}
+
+impl Display for TurtleGraphConfig {
+    /// Writes the grammar back on the `.lsy` format with its comments, one entry per
+    /// line in the order angle, order, rotate, the jitter, tropism, region, the
+    /// draws, axiom, the rules sorted by symbol and the interpretations.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entry = |f: &mut std::fmt::Formatter<'_>, entry: Entry, value: String| {
+            for comment in self.comments.above.get(&entry).into_iter().flatten() {
+                writeln!(f, "{}", format!("; {}", comment).trim_end())?;
+            }
+            match self.comments.inline.get(&entry) {
+                Some(comment) => writeln!(f, "{}", format!("{} ; {}", value, comment).trim_end()),
+                None => writeln!(f, "{}", value),
+            }
+        };
+        if let Some(angle) = self.angle {
+            entry(f, Entry::Angle, format!("angle {}", angle))?;
+        }
+        if let Some(order) = self.order {
+            entry(f, Entry::Order, format!("order {}", order))?;
+        }
+        if let Some(rotate) = self.rotate {
+            entry(f, Entry::Rotate, format!("rotate {}", rotate))?;
+        }
+        if let Some(angle_jitter) = self.angle_jitter {
+            entry(
+                f,
+                Entry::AngleJitter,
+                format!("angle_jitter {}", angle_jitter),
+            )?;
+        }
+        if let Some(length_jitter) = self.length_jitter {
+            entry(
+                f,
+                Entry::LengthJitter,
+                format!("length_jitter {}", length_jitter),
+            )?;
+        }
+        if let Some(seed) = self.seed {
+            entry(f, Entry::Seed, format!("seed {}", seed))?;
+        }
+        if let Some(tropism) = self.tropism {
+            entry(f, Entry::Tropism, format!("tropism {}", tropism))?;
+        }
+        if let Some(region) = &self.region {
+            entry(f, Entry::Region, format!("region {}", region))?;
+        }
+        for (symbol, command) in self.sorted_draws() {
+            entry(
+                f,
+                Entry::Draw(*symbol),
+                format!("draw {} {}", symbol, command),
+            )?;
+        }
+        let has_comments =
+            |e: &Entry| self.comments.above.contains_key(e) || self.comments.inline.contains_key(e);
+        if !self.axiom.is_empty() || has_comments(&Entry::Axiom) {
+            entry(
+                f,
+                Entry::Axiom,
+                format!("axiom {}", symbols_to_string(&self.axiom))
+                    .trim()
+                    .into(),
+            )?;
+        }
+        for (symbol, value) in self.sorted_rules() {
+            entry(
+                f,
+                Entry::Rule(*symbol),
+                format!("{} = {}", symbol, symbols_to_string(value))
+                    .trim()
+                    .into(),
+            )?;
+        }
+        for (i, rule) in self.conditional_rules.iter().enumerate() {
+            entry(f, Entry::ConditionalRule(i), rule.to_string().trim().into())?;
+        }
+        for (symbol, value) in self.sorted_interpretations() {
+            entry(
+                f,
+                Entry::Interpret(*symbol),
+                format!("interpret {} = {}", symbol, symbols_to_string(value))
+                    .trim()
+                    .into(),
+            )?;
+        }
+        for comment in self.comments.above.get(&Entry::End).into_iter().flatten() {
+            writeln!(f, "{}", format!("; {}", comment).trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl TurtleGraphConfig {
+    /// The rules sorted by symbol, so grammars are always written the same way.
+    pub(crate) fn sorted_rules(&self) -> Vec<(&TurtleSymbol, &Vec<TurtleSymbol>)> {
+        let mut rules: Vec<_> = self.rules.iter().collect();
+        rules.sort_by_key(|(symbol, _)| symbol.to_string());
+        rules
+    }
+
+    pub(crate) fn sorted_interpretations(&self) -> Vec<(&TurtleSymbol, &Vec<TurtleSymbol>)> {
+        let mut interpretations: Vec<_> = self.interpretations.iter().collect();
+        interpretations.sort_by_key(|(symbol, _)| symbol.to_string());
+        interpretations
+    }
+
+    /// The draws sorted by symbol, like the rules.
+    pub(crate) fn sorted_draws(&self) -> Vec<(&TurtleSymbol, &TurtleSymbol)> {
+        let mut draws: Vec<_> = self.draws.iter().collect();
+        draws.sort_by_key(|(symbol, _)| symbol.to_string());
+        draws
+    }
+
+    /// Applies a single line of a grammar file to the config, `number` is the line
+    /// reported on errors. Returns the entry defined by the line, if any.
+    pub fn parse_line(&mut self, line: &str, number: usize) -> Result<Option<Entry>, ParseError> {
+        let line = line.trim();
+        let line_end = line.find(';').unwrap_or(line.len());
+        let word = line[0..line_end].trim();
+        let comment = line.get(line_end + 1..).map(str::trim);
+        if word.is_empty() {
+            if let Some(comment) = comment {
+                self.comments.pending.push(comment.into());
+            }
+            return Ok(None);
+        }
+        let invalid = |message| ParseError {
+            line: number,
+            message,
+        };
+        // `angle_jitter` goes first since it also starts with `angle`.
+        let entry = if word.starts_with("angle_jitter") {
+            self.angle_jitter = parse_value(word, "angle_jitter", number)?;
+            Entry::AngleJitter
+        } else if word.starts_with("length_jitter") {
+            self.length_jitter = parse_value(word, "length_jitter", number)?;
+            Entry::LengthJitter
+        } else if word.starts_with("seed") {
+            self.seed = parse_value(word, "seed", number)?;
+            Entry::Seed
+        } else if word.starts_with("tropism") {
+            self.tropism = parse_value(word, "tropism", number)?;
+            Entry::Tropism
+        } else if let Some(region) = word.strip_prefix("region") {
+            let region =
+                Region::parse_in(region, &self.directory).map_err(|message| ParseError {
+                    line: number,
+                    message,
+                })?;
+            self.region = Some(region);
+            Entry::Region
+        } else if let Some(draw) = word.strip_prefix("draw") {
+            let (symbol, command) = parse_draw(draw).ok_or_else(|| ParseError {
+                line: number,
+                message: format!(
+                    "invalid draw `{}`, expected a symbol and one of F, G, + or -",
+                    word
+                ),
+            })?;
+            self.draws.insert(symbol, command);
+            Entry::Draw(symbol)
+        } else if let Some(rule) = word.strip_prefix("interpret") {
+            let rule = rule.replace(" ", "");
+            let head = parse_symbol(&rule).map_err(invalid)?;
+            let (symbol, value) = head
+                .and_then(|(symbol, rest)| Some((symbol, rest.strip_prefix('=')?)))
+                .ok_or_else(|| ParseError {
+                    line: number,
+                    message: format!(
+                        "invalid interpretation `{}`, expected one like `interpret L = [+G-G]`",
+                        word
+                    ),
+                })?;
+            self.interpretations
+                .insert(symbol, parse_symbols(value).map_err(invalid)?);
+            Entry::Interpret(symbol)
+        } else if word.starts_with("angle") {
+            self.angle = parse_value(word, "angle", number)?;
+            Entry::Angle
+        } else if word.starts_with("order") {
+            self.order = parse_value(word, "order", number)?;
+            Entry::Order
+        } else if word.starts_with("rotate") {
+            self.rotate = parse_value(word, "rotate", number)?;
+            Entry::Rotate
+        } else if let Some(axiom) = word.strip_prefix("axiom") {
+            self.axiom = parse_symbols(axiom.trim()).map_err(invalid)?;
+            Entry::Axiom
+        } else if matches!(
+            parse_symbol(&word.replace(" ", "")).map_err(invalid)?,
+            Some((_, rest)) if rest.starts_with(':')
+        ) {
+            let rule = word.replace(" ", "");
+            let (symbol, condition, value) = ConditionalRule::parse_head(&rule)
+                .and_then(|(symbol, condition, rest)| {
+                    Some((symbol, condition, rest.strip_prefix('=')?))
+                })
+                .ok_or_else(|| ParseError {
+                    line: number,
+                    message: format!(
+                        "invalid conditional rule `{}`, expected one like `F : t < 3 = FF`",
+                        word
+                    ),
+                })?;
+            self.conditional_rules.push(ConditionalRule {
+                symbol,
+                condition,
+                value: parse_symbols(value).map_err(invalid)?,
+            });
+            Entry::ConditionalRule(self.conditional_rules.len() - 1)
+        } else {
+            let rule = word.replace(" ", "");
+            let head = parse_symbol(&rule).map_err(invalid)?;
+            if let Some((symbol, Some(value))) =
+                head.map(|(symbol, rest)| (symbol, rest.strip_prefix('=')))
+            {
+                self.rules
+                    .insert(symbol, parse_symbols(value).map_err(invalid)?);
+                Entry::Rule(symbol)
+            } else {
+                return Err(ParseError {
+                    line: number,
+                    message: format!(
+                        "expected a directive or a rule like `F = F+F`, found `{}`",
+                        word
+                    ),
+                });
+            }
+        };
+        let pending = std::mem::take(&mut self.comments.pending);
+        if !pending.is_empty() {
+            self.comments
+                .above
+                .entry(entry)
+                .or_default()
+                .extend(pending);
+        }
+        if let Some(comment) = comment {
+            self.comments.inline.insert(entry, comment.into());
+        }
+        Ok(Some(entry))
+    }
+
+    fn jitter(&self) -> Option<Jitter> {
+        let angle = self.angle_jitter.unwrap_or(0);
+        let length = self.length_jitter.unwrap_or(0);
+        if angle == 0 && length == 0 {
+            return None;
+        }
+        Some(Jitter {
+            angle: angle as f32 / 100.0,
+            length: length as f32 / 100.0,
+            seed: self.seed.unwrap_or(DEFAULT_SEED),
+        })
+    }
+
+    /// The syntax of the axiom alone, before any rule is applied.
+    pub fn axiom_syntax(&self) -> TurtleSyntax {
+        TurtleSyntax {
             list: self.axiom.clone().into_iter().collect(),
             angle: self.angle.unwrap_or(0) as f32,
             order: self.order.unwrap_or(0),
             rotate: self.rotate.unwrap_or(0) as f32,
-        };
-        if let Some(order) = self.order {
-            for i in 0..order {
-                for (symbol, value) in &self.rules {
-                    syntax.apply_axiom(symbol, value);
-                }
+            jitter: self.jitter(),
+            tropism: self.tropism,
+            draws: self.draws.clone(),
+        }
+    }
+
+    /// The last generation with the interpretations applied, ready to be drawn.
+    pub fn generate_syntax(&self) -> TurtleSyntax {
+        self.generate_with(threads())
+    }
+
+    /// `generate_syntax` rewriting each generation on up to `threads` threads.
+    pub(crate) fn generate_with(&self, threads: usize) -> TurtleSyntax {
+        let mut syntax = self.derive_with(threads);
+        if !self.interpretations.is_empty() {
+            syntax.apply_rules_parallel(&self.interpretations, threads);
+        }
+        syntax
+    }
+
+    /// The last generation as the rules left it.
+    pub fn derive_syntax(&self) -> TurtleSyntax {
+        self.derive_with(threads())
+    }
+
+    pub(crate) fn derive_with(&self, threads: usize) -> TurtleSyntax {
+        let mut syntax = self.axiom_syntax();
+        if !self.conditional_rules.is_empty() {
+            syntax.list = self.generate_conditional().into_iter().collect();
+            return syntax;
+        }
+        // With a region every step is drawn to find where each `?` ended up.
+        let prunes = self.prunes();
+        for _ in 0..self.order.unwrap_or(0) {
+            syntax.apply_rules_parallel(&self.rules, threads);
+            if let Some(region) = &self.region {
+                syntax.query(region);
+            }
+            if prunes {
+                syntax.cut();
             }
         }
         syntax
     }
+
+    /// Expansion keeping the age of every symbol, which conditional rules need.
+    fn generate_conditional(&self) -> Vec<TurtleSymbol> {
+        let prunes = self.prunes();
+        let mut symbols: Vec<(TurtleSymbol, usize)> =
+            self.axiom.iter().map(|symbol| (*symbol, 0)).collect();
+        for time in 0..self.order.unwrap_or(0) as usize {
+            let positions = match self.queries_position() {
+                true => self.query_positions(symbols.iter().map(|(symbol, _)| *symbol)),
+                false => Vec::new(),
+            };
+            let mut positions = positions.into_iter();
+            symbols = symbols
+                .into_iter()
+                .flat_map(|(symbol, age)| {
+                    let position = match symbol {
+                        TurtleSymbol::Query => positions.next(),
+                        _ => None,
+                    };
+                    match self.production(&symbol, time, age, position) {
+                        Some(value) => value.iter().map(|symbol| (*symbol, 0)).collect(),
+                        None => vec![(symbol, age + 1)],
+                    }
+                })
+                .collect();
+            self.query_aged(&mut symbols);
+            if prunes {
+                let mut cutter = Cutter::default();
+                symbols.retain(|(symbol, _)| cutter.keep(symbol));
+            }
+        }
+        symbols.into_iter().map(|(symbol, _)| symbol).collect()
+    }
 }
 
-#[derive(Debug)]
+/// What the turtle did while walking over a syntax.
+#[derive(Debug, Default)]
+pub struct Walk {
+    pub segments: Vec<Segment>,
+    /// Deepest nesting of `[` reached.
+    pub max_depth: usize,
+    /// Branches closed by `]` after drawing something.
+    pub tips: usize,
+    /// Position of the turtle after the last symbol.
+    pub end: Point,
+    /// Heading after the last symbol in degrees, counterclockwise from the x axis.
+    pub heading: f32,
+    /// Position of the turtle at each `?`, in order.
+    pub queries: Vec<Point>,
+}
+
+#[derive(Debug, Clone)]
 struct TurtleSyntaxState {
     x: f32,
     y: f32,
@@ -143,19 +584,217 @@ struct TurtleSyntaxState {
     color: Colors,
 }
 
-impl TurtleSyntaxState {
-    fn new(x: f32, y: f32, z: f32, angle: f32, color: Colors) -> Self {
+/// The turtle between two symbols, everything a walk needs to go on from there.
+#[derive(Debug, Clone)]
+pub(crate) struct Turtle<'a> {
+    x: f32,
+    y: f32,
+    z: f32,
+    angle: f32,
+    color: Colors,
+    depth: usize,
+    drawn: bool,
+    history_stack: Vec<TurtleSyntaxState>,
+    random: Random,
+    base_angle: f32,
+    jitter: Jitter,
+    tropism: Option<Tropism>,
+    draws: &'a HashMap<TurtleSymbol, TurtleSymbol>,
+}
+
+impl<'a> Turtle<'a> {
+    pub(crate) fn new(syntax: &'a TurtleSyntax) -> Self {
+        let jitter = syntax.jitter.unwrap_or(Jitter {
+            angle: 0.0,
+            length: 0.0,
+            seed: DEFAULT_SEED,
+        });
         Self {
-            x,
-            y,
-            z,
-            angle,
-            color,
+            x: 0.0,
+            y: 0.0,
+            z: 100.0,
+            angle: 0.0,
+            color: Colors::Black,
+            depth: 0,
+            drawn: false,
+            history_stack: Vec::new(),
+            random: Random::new(jitter.seed),
+            base_angle: TAU / syntax.angle,
+            jitter,
+            tropism: syntax
+                .tropism
+                .map(|tropism| tropism.unrotated(syntax.rotate)),
+            draws: &syntax.draws,
+        }
+    }
+
+    fn vary(&mut self, amount: f32) -> f32 {
+        if amount == 0.0 {
+            1.0
+        } else {
+            1.0 + amount * self.random.signed()
+        }
+    }
+
+    /// Steps forward drawing, returning the segment drawn.
+    pub(crate) fn forward(&mut self) -> Segment {
+        let from = Point::new(self.x, self.y);
+        self.jump();
+        self.drawn = true;
+        if let Some(tropism) = self.tropism {
+            self.angle = tropism.bend(self.angle);
+        }
+        Segment::new(from, Point::new(self.x, self.y), self.color)
+    }
+
+    /// Steps forward drawing `count` times, adding the segments to `segments`. The
+    /// heading only changes with a tropism, without one it is worked out once.
+    pub(crate) fn forward_run(&mut self, count: usize, segments: &mut Vec<Segment>) {
+        if self.tropism.is_some() {
+            segments.extend((0..count).map(|_| self.forward()));
+            return;
+        }
+        let (sin, cos) = self.angle.sin_cos();
+        for _ in 0..count {
+            let from = Point::new(self.x, self.y);
+            let step = self.z * self.vary(self.jitter.length);
+            self.x += cos * step;
+            self.y += sin * step;
+            segments.push(Segment::new(from, Point::new(self.x, self.y), self.color));
+        }
+        self.drawn |= count > 0;
+    }
+
+    /// Steps forward without drawing.
+    pub(crate) fn jump(&mut self) {
+        let step = self.z * self.vary(self.jitter.length);
+        self.x += self.angle.cos() * step;
+        self.y += self.angle.sin() * step;
+    }
+
+    /// Turns by the angle of the grammar, counterclockwise when `left`.
+    pub(crate) fn turn(&mut self, left: bool) {
+        let turn = self.base_angle * self.vary(self.jitter.angle);
+        if left {
+            self.angle += turn;
+        } else {
+            self.angle -= turn;
+        }
+    }
+
+    /// Saves the state for the next `]`, returning how deep the turtle now is.
+    pub(crate) fn push(&mut self) -> usize {
+        self.history_stack.push(TurtleSyntaxState {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            angle: self.angle,
+            color: self.color,
+        });
+        self.depth += 1;
+        self.drawn = false;
+        self.depth
+    }
+
+    /// Goes back to the state saved by the last `[`, returning whether the branch
+    /// closed drew something, or `None` when nothing was saved.
+    pub(crate) fn pop(&mut self) -> Option<bool> {
+        let state = self.history_stack.pop()?;
+        self.x = state.x;
+        self.y = state.y;
+        self.z = state.z;
+        self.angle = state.angle;
+        self.color = state.color;
+        self.depth -= 1;
+        Some(std::mem::replace(&mut self.drawn, false))
+    }
+
+    /// Whether the jitter or the tropism make each move depend on the moves before
+    /// it and not only on where the turtle stands.
+    pub(crate) fn varies(&self) -> bool {
+        self.jitter.angle != 0.0 || self.jitter.length != 0.0 || self.tropism.is_some()
+    }
+
+    /// Length of a step and angle of a turn without jitter.
+    pub(crate) fn units(&self) -> (f32, f32) {
+        (self.z, self.base_angle)
+    }
+
+    /// Moves by `motion`, measured from the position and heading of the turtle.
+    pub(crate) fn follow(&mut self, motion: &Motion) {
+        let (sin, cos) = self.angle.sin_cos();
+        self.x += cos * motion.x - sin * motion.y;
+        self.y += sin * motion.x + cos * motion.y;
+        self.angle += motion.angle;
+        if let Some(color) = motion.color {
+            self.color = color;
         }
+        self.drawn = motion.drawn || (self.drawn && !motion.closed);
+    }
+
+    pub(crate) fn position(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    pub(crate) fn set_color(&mut self, color: Colors) {
+        self.color = color;
+    }
+
+    /// Runs `symbol`, taking the digit of a `C` from `symbols`. What it draws goes
+    /// to `walk`, without one the turtle only moves.
+    pub(crate) fn step(
+        &mut self,
+        symbol: TurtleSymbol,
+        symbols: &mut impl Iterator<Item = TurtleSymbol>,
+        walk: Option<&mut Walk>,
+    ) {
+        // The heading and position varied by the jitter are saved by `[` like the
+        // exact ones, the random sequence itself goes on across branches.
+        match symbol.command(self.draws) {
+            TurtleSymbol::F => {
+                let segment = self.forward();
+                if let Some(walk) = walk {
+                    walk.segments.push(segment);
+                }
+            }
+            TurtleSymbol::G => self.jump(),
+            TurtleSymbol::Plus => self.turn(true),
+            TurtleSymbol::Minus => self.turn(false),
+            TurtleSymbol::PushStack => {
+                let depth = self.push();
+                if let Some(walk) = walk {
+                    walk.max_depth = walk.max_depth.max(depth);
+                }
+            }
+            TurtleSymbol::PopStack => {
+                if let (Some(true), Some(walk)) = (self.pop(), walk) {
+                    walk.tips += 1;
+                }
+            }
+            TurtleSymbol::Query => {
+                if let Some(walk) = walk {
+                    walk.queries.push(self.position());
+                }
+            }
+            TurtleSymbol::CustomSymbol('C') => {
+                if let Some(TurtleSymbol::CustomSymbol(color_value)) = symbols.next() {
+                    if let Some(value) = Colors::from_digit(color_value) {
+                        self.color = value;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Records where the turtle stopped.
+    pub(crate) fn finish(&self, walk: &mut Walk) {
+        walk.end = Point::new(self.x, self.y);
+        walk.heading = self.angle.to_degrees();
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Colors {
     Red,
     Green,
@@ -166,31 +805,81 @@ pub enum Colors {
     White,
 }
 
+impl Colors {
+    /// The color selected by the digit following a `C` symbol.
+    pub fn from_digit(c: char) -> Option<Self> {
+        match c {
+            '0' => Some(Colors::Black),
+            '1' => Some(Colors::Red),
+            '2' => Some(Colors::DarkBlue),
+            '3' => Some(Colors::Green),
+            '4' => Some(Colors::Brown),
+            '5' => Some(Colors::DarkGreen),
+            '6' => Some(Colors::White),
+            _ => None,
+        }
+    }
+
+    pub fn rgb(&self) -> (f32, f32, f32) {
+        match self {
+            Colors::Red => (1.0, 0.0, 0.0),
+            Colors::Green => (0.0, 1.0, 0.0),
+            Colors::DarkBlue => (0.0, 0.0, 1.0),
+            Colors::Black => (0.0, 0.0, 0.0),
+            Colors::Brown => (0.7, 0.3, 0.0),
+            Colors::DarkGreen => (0.0, 0.5, 0.0),
+            Colors::White => (1.0, 1.0, 1.0),
+        }
+    }
+}
+
 impl Display for Colors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let color_value = match self {
-            Colors::Red => "1 0 0",
-            Colors::Green => "0 1 0",
-            Colors::DarkBlue => "0 0 1",
-            Colors::Black => "0 0 0",
-            Colors::Brown => "0.7 0.3 0",
-            Colors::DarkGreen => "0.0 0.5 0.0",
-            Colors::White => "1 1 1",
-        };
-        write!(f, "{} setrgbcolor", color_value)
+        let (r, g, b) = self.rgb();
+        write!(f, "{} {} {} setrgbcolor", r, g, b)
+    }
+}
+
+/// Replaces every symbol of `list` that has a rule by its value. Symbols with an
+/// empty rule are erased first, a node can not take itself out of the list.
+pub(crate) fn rewrite(
+    list: &mut List<TurtleSymbol>,
+    rules: &HashMap<TurtleSymbol, Vec<TurtleSymbol>>,
+) {
+    if rules.values().any(Vec::is_empty) {
+        list.retain(|symbol| !rules.get(symbol).is_some_and(Vec::is_empty));
+    }
+    for node in list.iter_mut() {
+        if let Some(value) = rules.get(&node.data) {
+            node.replace_with_list(value.iter().copied().collect());
+        }
     }
 }
 
 impl TurtleSyntax {
-    pub fn apply_axiom(&mut self, symbol: &TurtleSymbol, value: &Vec<TurtleSymbol>) {
+    pub fn apply_axiom(&mut self, symbol: &TurtleSymbol, value: &[TurtleSymbol]) {
+        if value.is_empty() {
+            return self.list.retain(|data| data != symbol);
+        }
         for node in self.list.iter_mut() {
             if &node.data == symbol {
-                node.replace_with_list(value.clone().into_iter().collect());
+                node.replace_with_list(value.iter().copied().collect());
             }
         }
     }
 
+    /// Rewrites every symbol of the current generation at once, so symbols
+    /// produced by one rule are not rewritten again by another in the same step.
+    pub fn apply_rules(&mut self, rules: &HashMap<TurtleSymbol, Vec<TurtleSymbol>>) {
+        rewrite(&mut self.list, rules);
+    }
+
     pub fn convert(&self) -> String {
+        self.postscript(&self.content())
+    }
+
+    /// Wraps a PostScript body with the preamble and the closing commands.
+    pub(crate) fn postscript(&self, content: &str) -> String {
         let mut value: String = include_str!("preamble/preamble_header.txt").into();
         let base_order = std::env::var("BASE_ORDER")
             .unwrap_or("1".into())
@@ -203,93 +892,74 @@ impl TurtleSyntax {
             self.rotate
         ));
         value.push_str(include_str!("preamble/preamble_content.txt"));
-        value.push_str(&self.content());
+        value.push_str(content);
         value.push_str("stroke\n\ngrestore\n\nshowpage\nquit\n");
         value
     }
     fn content(&self) -> String {
         let mut value = String::new();
-        let mut x = 0f32;
-        let mut y = 0f32;
-        let mut z = 100f32;
-        let mut color = Colors::Black;
-        let base_angle = TAU / self.angle;
-        let mut angle = 0f32;
-        let mut history_stack: List<TurtleSyntaxState> = List::new();
-        let mut iter = self.list.iter();
-        while let Some(node) = iter.next() {
-            match node.data {
-                TurtleSymbol::F => {
-                    value.push_str(&format!("{}\n", color));
-                    value.push_str(&format!("n {:.2} {:.2} ", x, y));
-                    x += angle.cos() * z;
-                    y += angle.sin() * z;
-                    value.push_str(&format!("m {:.2} {:.2} l s\n", x, y));
-                }
-                TurtleSymbol::G => {
-                    x += angle.cos() * z;
-                    y += angle.sin() * z;
-                }
-                TurtleSymbol::Plus => {
-                    angle += base_angle;
-                }
-                TurtleSymbol::Minus => {
-                    angle -= base_angle;
-                }
-                TurtleSymbol::PushStack => {
-                    history_stack.push(TurtleSyntaxState::new(x, y, z, angle, color));
-                }
-                TurtleSymbol::PopStack => {
-                    if let Some(state) = history_stack.pop_tail() {
-                        x = state.x;
-                        y = state.y;
-                        z = state.z;
-                        angle = state.angle;
-                        color = state.color;
-                    }
-                }
-                TurtleSymbol::CustomSymbol(c) if c == 'C' => {
-                    if let Some(node) = iter.next() {
-                        if let TurtleSymbol::CustomSymbol(color_value) = node.data {
-                            match color_value {
-                                '0' => color = Colors::Black,
-                                '1' => color = Colors::Red,
-                                '2' => color = Colors::DarkBlue,
-                                '3' => color = Colors::Green,
-                                '4' => color = Colors::Brown,
-                                '5' => color = Colors::DarkGreen,
-                                '6' => color = Colors::White,
-                                _ => {}
-                            }
-                        }
-                    }
+        let mut color = None;
+        for path in optimize(&self.segments()) {
+            if color != Some(path.color) {
+                let _ = writeln!(value, "{}", path.color);
+                color = Some(path.color);
+            }
+            let mut points = path.points.iter();
+            if let Some(start) = points.next() {
+                let _ = write!(value, "n {:.2} {:.2} m", start.x, start.y);
+            }
+            for (i, point) in points.enumerate() {
+                if i % 8 == 7 {
+                    value.push('\n');
                 }
-                _ => {}
+                let _ = write!(value, " {:.2} {:.2} l", point.x, point.y);
             }
+            value.push_str(" s\n");
         }
         value
     }
 
+    /// Every line drawn by the turtle, in order.
+    pub fn segments(&self) -> Vec<Segment> {
+        self.walk().segments
+    }
+
+    /// Walks the turtle over the symbols, recording what it draws and where it ends.
+    pub fn walk(&self) -> Walk {
+        self.walk_parallel(threads())
+    }
+
+    /// Walks the turtle over `symbols` with the settings of this syntax.
+    pub(crate) fn walk_symbols(&self, mut symbols: impl Iterator<Item = TurtleSymbol>) -> Walk {
+        let mut turtle = Turtle::new(self);
+        let mut walk = Walk::default();
+        while let Some(symbol) = symbols.next() {
+            turtle.step(symbol, &mut symbols, Some(&mut walk));
+        }
+        turtle.finish(&mut walk);
+        walk
+    }
+
     pub fn string(&self) -> String {
-        self.list
-            .iter()
-            .map(|node| format!("{}", node.data))
-            .collect::<Vec<String>>()
-            .join("")
+        let mut value = String::new();
+        for node in self.list.iter() {
+            let _ = write!(value, "{}", node.data);
+        }
+        value
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    const helloworld: &str = r#"angle 8 ; means 360/8
+    const HELLOWORLD: &str = r#"angle 8 ; means 360/8
 order 2
 axiom ++F
 F = F+F
     "#;
     #[test]
     fn basic_config() {
-        let preamble = TurtleGraphConfig::from_str(helloworld).unwrap();
+        let preamble = TurtleGraphConfig::from_str(HELLOWORLD).unwrap();
         assert_eq!(preamble.angle, Some(8));
         assert_eq!(preamble.order, Some(2));
         assert_eq!(preamble.rotate, None);
@@ -297,7 +967,73 @@ F = F+F
             preamble.axiom,
             vec![TurtleSymbol::Plus, TurtleSymbol::Plus, TurtleSymbol::F]
         );
-        dbg!(&preamble);
-        dbg!(preamble.generate_syntax());
+        assert_eq!(preamble.generate_syntax().string(), "++F+F+F+F");
+    }
+
+    #[test]
+    fn empty_rule() {
+        let text = "angle 4\norder 2\naxiom FXF\nX = \n";
+        let config = TurtleGraphConfig::from_str(text).unwrap();
+        assert_eq!(config.generate_syntax().string(), "FF");
+        let conditional = TurtleGraphConfig::from_str(&format!("{}G : t > 9 = G", text)).unwrap();
+        assert_eq!(conditional.generate_syntax().string(), "FF");
+        let mut syntax = TurtleGraphConfig::from_str("axiom XFX")
+            .unwrap()
+            .axiom_syntax();
+        syntax.apply_axiom(&TurtleSymbol::CustomSymbol('X'), &[]);
+        syntax.list.push(TurtleSymbol::G);
+        assert_eq!(syntax.string(), "FG");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            TurtleGraphConfig::from_str("angle 8\norder two\n").unwrap_err(),
+            ParseError {
+                line: 2,
+                message: "invalid value `two` for order".into()
+            }
+        );
+        assert_eq!(
+            TurtleGraphConfig::from_str("; comment\n\nF + F\n")
+                .unwrap_err()
+                .line,
+            3
+        );
+    }
+
+    #[test]
+    fn jitter() {
+        let grammar = "angle 4\norder 2\naxiom F\nF = F[+F]-F\nlength_jitter 20\nangle_jitter 10";
+        let config = TurtleGraphConfig::from_str(grammar).unwrap();
+        let segments = config.generate_syntax().segments();
+        assert_eq!(segments, config.generate_syntax().segments());
+        let lengths: Vec<f32> = segments
+            .iter()
+            .map(|s| (s.to.x - s.from.x).hypot(s.to.y - s.from.y))
+            .collect();
+        assert!(lengths.iter().all(|l| (80.0..=120.0).contains(l)));
+        assert!(lengths.iter().any(|l| (l - 100.0).abs() > 1.0));
+        // `]` goes back to where the branch started, jitter and all.
+        assert_eq!(segments[1].from, segments[0].to);
+        assert_eq!(segments[2].from, segments[0].to);
+
+        let reseeded = TurtleGraphConfig::from_str(&format!("{}\nseed 2", grammar)).unwrap();
+        assert_ne!(reseeded.generate_syntax().segments(), segments);
+        assert!(reseeded
+            .to_string()
+            .contains("angle_jitter 10\nlength_jitter 20\nseed 2\n"));
+    }
+
+    #[test]
+    fn interpret() {
+        let text = "order 2\naxiom A\nA = FAL\ninterpret L = [+F]\n";
+        let config = TurtleGraphConfig::from_str(text).unwrap();
+        assert_eq!(config.to_string(), text);
+        assert_eq!(config.derive_syntax().string(), "FFALL");
+        assert_eq!(config.generate_syntax().string(), "FFA[+F][+F]");
+        assert_eq!(config.generation_syntax().string(), "C1FC2FA[+C2F][+C1F]");
+        assert_eq!(config.growth().segments(2).to_string(), "4");
+        assert_eq!(config.growth().syntax_size(2).to_string(), "11");
     }
 }